@@ -1,5 +1,13 @@
-# mint domain
-near call nft.gnet.testnet nft_mint '{ "token_id": "manhng.btc", "metadata": { "title": "manhng.btc", "description": "bitcoin domain provided by decentrailnet", "media": "https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg" }, "receiver_id": "manhng.testnet" }' --accountId manhng.testnet --amount 0.2
+# register a domain: commit to sha256("{name}:{owner_id}:{secret}") first, then reveal it at least a minute later
+near view nft.gnet.testnet make_commitment '{"name": "manhng.btc", "owner_id": "manhng.testnet", "secret": "<random secret>"}'
+near call nft.gnet.testnet commit '{"commitment": "<base64 commitment>"}' --accountId manhng.testnet --amount 0.2
+near call nft.gnet.testnet reveal_and_register '{"name": "manhng.btc", "secret": "<random secret>", "metadata": { "title": "manhng.btc", "description": "bitcoin domain provided by decentrailnet", "media": "https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg" }}' --accountId manhng.testnet
+
+# take back a commitment and its deposit, e.g. after its reveal failed
+near call nft.gnet.testnet cancel_commitment '{"commitment": "<base64 commitment>"}' --depositYocto 1 --accountId manhng.testnet
+
+# the owner mints reserved names directly, here for 3 years. The excess deposit is refunded
+near call nft.gnet.testnet nft_mint '{ "token_id": "manhng.btc", "metadata": { "title": "manhng.btc" }, "receiver_id": "manhng.testnet", "years": 3 }' --accountId nft.gnet.testnet --amount 0.6
 
 # get address buy token id and network
 near view nft.gnet.testnet get_address '{"token_id": "manhng.btc", "network": "Ethereum"}'
@@ -17,4 +25,49 @@ near near call nft.gnet.testnet insert_addresses '{"token_id": "manhng.btc", "ad
 near call nft.gnet.testnet reset_token_addresses '{"token_id": "manhng.btc"}' --accountId manhng.testnet
 
 # extend token
-near call nft.gnet.testnet extend_token '{"token_id": "manhng.btc"}' --accountId manhng.testnet --deposit 0.6
\ No newline at end of file
+near call nft.gnet.testnet extend_token '{"token_id": "manhng.btc"}' --accountId manhng.testnet --deposit 0.6
+
+# set text records
+near call nft.gnet.testnet set_text_records '{"token_id": "manhng.btc", "records": [{"key": "com.twitter", "value": "manhng"}]}' --deposit 0.003 --accountId manhng.testnet
+
+# get a text record
+near view nft.gnet.testnet get_text '{"token_id": "manhng.btc", "key": "com.twitter"}'
+
+# point a name to a website
+near call nft.gnet.testnet set_contenthash '{"token_id": "manhng.btc", "contenthash": {"ipfs": "QmRAQB6YaCyidP37UdDnjFY5vQuiBrcqdyoW1CuDgwxkD4"}}' --deposit 0.003 --accountId manhng.testnet
+near view nft.gnet.testnet get_contenthash '{"token_id": "manhng.btc"}'
+
+# resolve a name through a custom resolver contract
+near call nft.gnet.testnet set_resolver '{"token_id": "manhng.btc", "resolver_id": "resolver.gnet.testnet"}' --deposit 0.003 --accountId manhng.testnet
+near call nft.gnet.testnet resolve_via_resolver '{"name": "manhng.btc", "key": "addr:Ethereum"}' --accountId manhng.testnet
+
+# resolve many names at once
+near view nft.gnet.testnet batch_resolve '{"queries": [{"token_id": "manhng.btc", "networks": ["Ethereum", "NEAR"]}]}'
+
+# pay for record storage up front instead of attaching a deposit to every call
+near call nft.gnet.testnet storage_deposit '{}' --deposit 0.1 --accountId manhng.testnet
+near view nft.gnet.testnet storage_balance_of '{"account_id": "manhng.testnet"}'
+
+# credit a partner wallet with a share of the payment, then let it claim its rewards
+near call nft.gnet.testnet set_referral_rate '{"referral_rate": 1000}' --accountId nft.gnet.testnet
+near call nft.gnet.testnet reveal_and_register '{"name": "manhng2.btc", "secret": "<random secret>", "metadata": { "title": "manhng2.btc" }, "referrer": "wallet.testnet"}' --accountId manhng.testnet
+near view nft.gnet.testnet get_referrer_rewards '{"account_id": "wallet.testnet"}'
+near call nft.gnet.testnet claim_referral_rewards '{}' --depositYocto 1 --accountId wallet.testnet
+
+# allowlist presale for a TLD launch: leaves are sha256("{account_id}:{name}") or sha256("{account_id}:{quota}")
+near call nft.gnet.testnet set_presale '{"presale": {"tld": "btc", "merkle_root": "<base64 root>", "starts_at": "1672531200000000000", "ends_at": "1673136000000000000", "discount": 2000}}' --accountId nft.gnet.testnet
+near call nft.gnet.testnet nft_mint_presale '{ "token_id": "manhng.btc", "metadata": { "title": "manhng.btc" }, "receiver_id": "manhng.testnet", "allowance": {"name": "manhng.btc"}, "proof": ["<base64 sibling hash>"] }' --accountId manhng.testnet --amount 0.2
+
+# pay for registrations and renewals in a fungible token, e.g. USDC
+near call nft.gnet.testnet set_ft_price_tiers '{"token_account_id": "usdc.testnet", "price_tiers": {"one_two_chars": "1000000000", "three_chars": "500000000", "four_chars": "100000000", "five_plus_chars": "10000000"}}' --accountId nft.gnet.testnet
+near call usdc.testnet ft_transfer_call '{"receiver_id": "nft.gnet.testnet", "amount": "10000000", "msg": "{\"action\": \"mint\", \"token_id\": \"manhng3.btc\", \"metadata\": {\"title\": \"manhng3.btc\"}, \"secret\": \"<random secret>\"}"}' --depositYocto 1 --gas 100000000000000 --accountId manhng.testnet
+near call usdc.testnet ft_transfer_call '{"receiver_id": "nft.gnet.testnet", "amount": "10000000", "msg": "{\"action\": \"renew\", \"token_id\": \"manhng3.btc\"}"}' --depositYocto 1 --gas 100000000000000 --accountId manhng.testnet
+near view nft.gnet.testnet get_ft_revenue '{"token_account_id": "usdc.testnet"}'
+
+# price names in USD cents, paid in NEAR at the oracle's NEAR/USD rate
+near call nft.gnet.testnet set_usd_pricing '{"usd_pricing": {"oracle_id": "oracle.testnet", "price_tiers": {"one_two_chars": "50000", "three_chars": "10000", "four_chars": "2000", "five_plus_chars": "500"}, "max_quote_age": "60000000000"}}' --accountId nft.gnet.testnet
+near call nft.gnet.testnet nft_mint_usd '{ "token_id": "manhng4.btc", "metadata": { "title": "manhng4.btc" }, "receiver_id": "manhng.testnet", "years": 1, "expected_price": "500", "slippage": 100, "secret": "<random secret>" }' --accountId manhng.testnet --amount 2 --gas 100000000000000
+near call nft.gnet.testnet extend_token_usd '{"token_id": "manhng4.btc", "expected_price": "500", "slippage": 100}' --accountId manhng.testnet --amount 1 --gas 100000000000000
+
+# yearly price of a name, by the length of its label
+near view nft.gnet.testnet nft_name_price_per_year '{"token_id": "manhng.btc"}'
@@ -1 +1 @@
-near call nft.gnet.testnet nft_mint '{ "token_id": "manhng.btc", "metadata": { "title": "manhng.btc", "description": "bitcoin domain provided by decentrailnet", "media": "https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg" }, "receiver_id": "manhng.testnet" }' --accountId manhng.testnet --amount 0.2
\ No newline at end of file
+near call nft.gnet.testnet nft_mint '{ "token_id": "manhng.btc", "metadata": { "title": "manhng.btc", "description": "bitcoin domain provided by decentrailnet", "media": "https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg" }, "receiver_id": "manhng.testnet" }' --accountId nft.gnet.testnet --amount 0.2
\ No newline at end of file
@@ -1,6 +1,7 @@
 use crate::*;
 
-/// Supported netword domain enum.
+/// Networks supported before the chain registry. They are registered as chains under
+/// their `to_string()` when the contract is initialized or migrated.
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Network {
@@ -32,14 +33,14 @@ impl std::fmt::Display for Network {
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AddressInput {
-    pub network: Network,
+    pub network: ChainKey,
     pub address: String
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AddressRemoveInput {
-    pub network: Network,
+    pub network: ChainKey,
 }
 
 #[near_bindgen]
@@ -48,8 +49,8 @@ impl Contract {
     pub fn insert_addresses(&mut self, token_id: TokenId, addresses_input: Vec<AddressInput>) {
 
         let initial_storage_usage = env::storage_usage();
-        // assert if function caller is not the owner
-        self.assert_token_owner(&token_id);
+        // assert if function caller is not the owner or manager
+        self.assert_token_owner_or_manager(&token_id);
 
         self.assert_token_expires(&token_id);
 
@@ -62,21 +63,33 @@ impl Contract {
         };
 
         // insert into the token_addresses object
+        let mut addr_set_logs = Vec::new();
         for input in addresses_input.iter() {
+            let chain = self.assert_chain_active(&input.network);
+            chain.address_format
+                .validate(&input.address)
+                .unwrap_or_else(|reason| panic!("INVALID_ADDRESS: {}: {}", input.network, reason));
 
             // insert into token_addresses object
-            token_addresses.insert(input.network.to_string(), input.address.to_string());
+            token_addresses.insert(input.network.clone(), input.address.to_string());
+            addr_set_logs.push(AddrSetLog {
+                token_id: token_id.clone(),
+                network: input.network.clone(),
+                address: input.address.clone(),
+            });
         };
 
         // override token_addresses object
         self.addresses_by_token_id.insert(&token_id, &token_addresses);
+        if !addr_set_logs.is_empty() {
+            log_name_service_event(EventLogVariant::AddrSet(addr_set_logs));
+        }
 
-        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-
-        refund_deposit(required_storage_in_bytes);
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
     }
 
     pub fn get_token_addresses(&self, token_id: TokenId) -> HashMap<String, String> {
+        let token_id = normalize_token_id(&token_id);
         if self.is_token_expires(&token_id) {
             return HashMap::new();
         } else {
@@ -85,7 +98,9 @@ impl Contract {
         }
     }
 
-    pub fn get_address(&self, token_id: TokenId, network: Network) -> Option<String> {
+    pub fn get_address(&self, token_id: TokenId, network: ChainKey) -> Option<String> {
+        let token_id = normalize_token_id(&token_id);
+
         // check expires
         if self.is_token_expires(&token_id) {
             return None;
@@ -94,7 +109,7 @@ impl Contract {
         // get the token address object by token id
         match self.addresses_by_token_id.get(&token_id) {
             Some(address_by_token_id) => {
-                match address_by_token_id.get(&network.to_string()) {
+                match address_by_token_id.get(&network) {
                     Some(address) => {
                         Some(address.to_string())
                     },
@@ -110,27 +125,46 @@ impl Contract {
 
     #[payable]
     pub fn remove_addresses(&mut self, token_id: TokenId, addresses_input: Vec<AddressRemoveInput>) {
+        let initial_storage_usage = env::storage_usage();
         assert_one_yocto();
         let is_token_expires = self.is_token_expires(&token_id);
         if is_token_expires {
             self.assert_only_owner();
         } else {
-            self.assert_token_owner(&token_id);
+            self.assert_token_owner_or_manager(&token_id);
         }
         let mut token_addresses = self.addresses_by_token_id.get(&token_id).expect("NO_ADDRESSES_TOKEN");
+        let mut addr_removed_logs = Vec::new();
         for input in addresses_input.iter() {
-            token_addresses.remove(&input.network.to_string());
+            if token_addresses.remove(&input.network).is_some() {
+                addr_removed_logs.push(AddrRemovedLog { token_id: token_id.clone(), network: input.network.clone() });
+            }
         };
         self.addresses_by_token_id.insert(&token_id, &token_addresses);
+        if !addr_removed_logs.is_empty() {
+            log_name_service_event(EventLogVariant::AddrRemoved(addr_removed_logs));
+        }
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
     }
 
+    #[payable]
     pub fn reset_token_addresses(&mut self, token_id: TokenId) {
+        let initial_storage_usage = env::storage_usage();
         assert_one_yocto();
         
-        // assert if function caller is not the owner
-        self.assert_token_owner(&token_id);
+        // assert if function caller is not the owner or manager
+        self.assert_token_owner_or_manager(&token_id);
         
 
-        self.addresses_by_token_id.remove(&token_id);
+        if self.addresses_by_token_id.remove(&token_id).is_some() {
+            log_name_service_event(EventLogVariant::RecordsReset(vec![RecordsResetLog {
+                token_id: token_id.clone(),
+                epoch: self.record_epochs.get(&token_id).unwrap_or_default().epoch.to_string(),
+                record_type: Some("addr".to_string()),
+            }]));
+        }
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
     }
 }
\ No newline at end of file
@@ -0,0 +1,240 @@
+use crate::*;
+use bech32::{FromBase32, Variant};
+use blake2::{Blake2b, Digest};
+use near_sdk::bs58;
+
+//bitcoin base58check version bytes of pay-to-pubkey-hash ("1...") and pay-to-script-hash ("3...") addresses
+const BITCOIN_P2PKH_VERSION: u8 = 0x00;
+const BITCOIN_P2SH_VERSION: u8 = 0x05;
+const BITCOIN_HRP: &str = "bc";
+//tron base58check version byte, which makes every address start with "T"
+const TRON_VERSION: u8 = 0x41;
+const CARDANO_HRP: &str = "addr";
+const SS58_PREFIX: &[u8] = b"SS58PRE";
+
+impl AddressFormat {
+    /// Checks an address is well-formed for chains using this format, so a typo or an
+    /// address of another chain can't be recorded. The error says what is wrong with it.
+    pub fn validate(&self, address: &str) -> Result<(), String> {
+        match self {
+            AddressFormat::Evm => validate_evm(address),
+            AddressFormat::Bitcoin => validate_bitcoin(address),
+            AddressFormat::Solana => validate_solana(address),
+            AddressFormat::Substrate => validate_substrate(address),
+            AddressFormat::Cardano => validate_cardano(address),
+            AddressFormat::Tron => validate_tron(address),
+            AddressFormat::Near => address
+                .parse::<AccountId>()
+                .map(|_| ())
+                .map_err(|_| "not a valid NEAR account id".to_string()),
+            AddressFormat::Cosmos => validate_cosmos(address),
+            AddressFormat::Other => {
+                if address.is_empty() {
+                    Err("address is empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+//0x-prefixed hex with the EIP-55 mixed-case checksum
+fn validate_evm(address: &str) -> Result<(), String> {
+    let hex = address
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or("must be 0x followed by 40 hex characters")?;
+
+    let hash = env::keccak256(hex.to_ascii_lowercase().as_bytes());
+    for (i, c) in hex.chars().enumerate() {
+        let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        if c.is_ascii_alphabetic() && c.is_ascii_uppercase() != (nibble >= 8) {
+            return Err("EIP-55 checksum mismatch".to_string());
+        }
+    }
+    Ok(())
+}
+
+//legacy base58check addresses or segwit bech32 (v0) / bech32m (v1+) addresses
+fn validate_bitcoin(address: &str) -> Result<(), String> {
+    if address.to_ascii_lowercase().starts_with("bc1") {
+        let (hrp, data, variant) = bech32::decode(address).map_err(|e| format!("invalid bech32: {}", e))?;
+        if hrp != BITCOIN_HRP {
+            return Err(format!("unexpected prefix {}", hrp));
+        }
+        let (version, program) = data.split_first().ok_or("missing witness version")?;
+        let program = Vec::<u8>::from_base32(program).map_err(|e| format!("invalid bech32: {}", e))?;
+        return match version.to_u8() {
+            0 if variant != Variant::Bech32 => Err("segwit v0 address must use bech32".to_string()),
+            0 if program.len() != 20 && program.len() != 32 => Err("segwit v0 program must be 20 or 32 bytes".to_string()),
+            1..=16 if variant != Variant::Bech32m => Err("segwit v1+ address must use bech32m".to_string()),
+            1..=16 if program.len() < 2 || program.len() > 40 => Err("segwit program must be 2 to 40 bytes".to_string()),
+            17.. => Err("unknown witness version".to_string()),
+            _ => Ok(()),
+        };
+    }
+
+    let payload = decode_base58check(address)?;
+    match payload.split_first() {
+        Some((&BITCOIN_P2PKH_VERSION | &BITCOIN_P2SH_VERSION, hash)) if hash.len() == 20 => Ok(()),
+        _ => Err("not a P2PKH or P2SH address".to_string()),
+    }
+}
+
+//base58 encoded ed25519 public key
+fn validate_solana(address: &str) -> Result<(), String> {
+    let bytes = decode_base58(address)?;
+    if bytes.len() != 32 {
+        return Err(format!("must decode to 32 bytes, got {}", bytes.len()));
+    }
+    Ok(())
+}
+
+//SS58: network prefix, 32 byte account id and the first 2 bytes of blake2b-512("SS58PRE" + prefix + account id)
+fn validate_substrate(address: &str) -> Result<(), String> {
+    let bytes = decode_base58(address)?;
+    let prefix_len = match bytes.first() {
+        Some(0..=63) => 1,
+        Some(64..=127) => 2,
+        _ => return Err("invalid SS58 network prefix".to_string()),
+    };
+    if bytes.len() != prefix_len + 32 + 2 {
+        return Err("must hold a 32 byte account id".to_string());
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 2);
+    let hash = Blake2b::new().chain(SS58_PREFIX).chain(body).finalize();
+    if hash[..2] != *checksum {
+        return Err("SS58 checksum mismatch".to_string());
+    }
+    Ok(())
+}
+
+//shelley "addr1..." bech32 address
+fn validate_cardano(address: &str) -> Result<(), String> {
+    let (hrp, _, variant) = bech32::decode(address).map_err(|e| format!("invalid bech32: {}", e))?;
+    if hrp != CARDANO_HRP {
+        return Err(format!("unexpected prefix {}", hrp));
+    }
+    if variant != Variant::Bech32 {
+        return Err("must use bech32".to_string());
+    }
+    Ok(())
+}
+
+//base58check "T..." address
+fn validate_tron(address: &str) -> Result<(), String> {
+    let payload = decode_base58check(address)?;
+    match payload.split_first() {
+        Some((&TRON_VERSION, hash)) if hash.len() == 20 => Ok(()),
+        _ => Err("not a Tron address".to_string()),
+    }
+}
+
+//bech32 account address under the chain's own prefix, e.g. "terra1..."
+fn validate_cosmos(address: &str) -> Result<(), String> {
+    let (_, data, variant) = bech32::decode(address).map_err(|e| format!("invalid bech32: {}", e))?;
+    if variant != Variant::Bech32 {
+        return Err("must use bech32".to_string());
+    }
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|e| format!("invalid bech32: {}", e))?;
+    if bytes.len() != 20 && bytes.len() != 32 {
+        return Err("must hold a 20 or 32 byte account".to_string());
+    }
+    Ok(())
+}
+
+fn decode_base58(address: &str) -> Result<Vec<u8>, String> {
+    bs58::decode(address).into_vec().map_err(|_| "invalid base58".to_string())
+}
+
+//base58 payload followed by the first 4 bytes of its double sha256
+fn decode_base58check(address: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = decode_base58(address)?;
+    if bytes.len() < 4 {
+        return Err("too short".to_string());
+    }
+
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if env::sha256(&env::sha256(&bytes))[..4] != checksum[..] {
+        return Err("base58check checksum mismatch".to_string());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bech32::ToBase32;
+
+    fn assert_invalid(format: AddressFormat, address: &str, reason: &str) {
+        let err = format.validate(address).unwrap_err();
+        assert!(err.contains(reason), "{:?}: expected {:?}, got {:?}", address, reason, err);
+    }
+
+    #[test]
+    fn evm_addresses() {
+        assert!(AddressFormat::Evm.validate("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_ok());
+        assert!(AddressFormat::Evm.validate("0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a").is_ok());
+        assert_invalid(AddressFormat::Evm, "0x5aaeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "EIP-55");
+        assert_invalid(AddressFormat::Evm, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA", "40 hex");
+        assert_invalid(AddressFormat::Evm, "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "40 hex");
+    }
+
+    #[test]
+    fn bitcoin_addresses() {
+        for address in [
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa",
+            "3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+        ] {
+            assert!(AddressFormat::Bitcoin.validate(address).is_ok(), "{}", address);
+        }
+        assert_invalid(AddressFormat::Bitcoin, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb", "checksum");
+        assert_invalid(AddressFormat::Bitcoin, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5", "invalid bech32");
+        assert_invalid(AddressFormat::Bitcoin, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", "P2PKH or P2SH");
+    }
+
+    #[test]
+    fn solana_addresses() {
+        assert!(AddressFormat::Solana.validate("So11111111111111111111111111111111111111112").is_ok());
+        assert_invalid(AddressFormat::Solana, "So1111111111111111111111111111111111111111", "32 bytes");
+        assert_invalid(AddressFormat::Solana, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "base58");
+    }
+
+    #[test]
+    fn substrate_addresses() {
+        assert!(AddressFormat::Substrate.validate("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5").is_ok());
+        assert!(AddressFormat::Substrate.validate("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").is_ok());
+        assert_invalid(AddressFormat::Substrate, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ", "checksum");
+        assert_invalid(AddressFormat::Substrate, "So11111111111111111111111111111111111111112", "32 byte");
+    }
+
+    #[test]
+    fn cardano_addresses() {
+        let address = "addr1qx2fxv2umyhttkxyxp8x0dlpdt3k6cwng5pxj3jhsydzer3n0d3vllmyqwsx5wktcd8cc3sq835lu7drv2xwl2wywfgse35a3x";
+        assert!(AddressFormat::Cardano.validate(address).is_ok());
+        assert_invalid(AddressFormat::Cardano, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "prefix");
+        assert_invalid(AddressFormat::Cardano, &address.replace("qx2", "qx3"), "invalid bech32");
+    }
+
+    #[test]
+    fn tron_addresses() {
+        assert!(AddressFormat::Tron.validate("TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t").is_ok());
+        assert_invalid(AddressFormat::Tron, "TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6u", "checksum");
+        assert_invalid(AddressFormat::Tron, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "not a Tron");
+    }
+
+    #[test]
+    fn near_and_cosmos_addresses() {
+        assert!(AddressFormat::Near.validate("alice.near").is_ok());
+        assert_invalid(AddressFormat::Near, "Alice.near", "NEAR account id");
+
+        let terra = bech32::encode("terra", [7u8; 20].to_base32(), Variant::Bech32).unwrap();
+        assert!(AddressFormat::Cosmos.validate(&terra).is_ok());
+        let short = bech32::encode("terra", [7u8; 10].to_base32(), Variant::Bech32).unwrap();
+        assert_invalid(AddressFormat::Cosmos, &short, "20 or 32 byte");
+    }
+}
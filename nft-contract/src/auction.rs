@@ -0,0 +1,90 @@
+use crate::*;
+
+/// Premium charged on top of the tier price for a name that just left its grace period.
+/// It starts at `start_premium` when the name is released and decays linearly to zero
+/// over `duration_days`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReleaseAuction {
+    pub start_premium: U128,
+    pub duration_days: u32,
+}
+
+impl Default for ReleaseAuction {
+    fn default() -> Self {
+        Self {
+            start_premium: U128(ONE_NEAR_ES_YOCTO * 1_000),
+            duration_days: 21,
+        }
+    }
+}
+
+/// Premium owed when registering a released name at `timestamp`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PremiumPoint {
+    pub timestamp: U64,
+    pub premium: U128,
+}
+
+impl ReleaseAuction {
+    fn premium_at(&self, released_at: u64, timestamp: u64) -> Balance {
+        //decay once per second, dividing before multiplying so large premiums cannot overflow
+        let duration = u128::from(self.duration_days) * u128::from(ONE_DAY_NANOSECOND / 1_000_000_000);
+        let elapsed = u128::from(timestamp.saturating_sub(released_at) / 1_000_000_000);
+        if elapsed >= duration {
+            return 0;
+        }
+        self.start_premium.0 - self.start_premium.0 / duration * elapsed
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_release_auction(&self) -> ReleaseAuction {
+        self.release_auction.clone()
+    }
+
+    pub fn set_release_auction(&mut self, release_auction: ReleaseAuction) {
+        self.assert_only_owner();
+        self.release_auction = release_auction;
+    }
+
+    //premium currently owed on top of the tier price to register this name
+    pub fn get_release_premium(&self, token_id: TokenId) -> U128 {
+        U128(self.internal_release_premium(&normalize_token_id(&token_id)))
+    }
+
+    //premium for each day of the auction, starting when the name leaves its grace period
+    pub fn get_release_premium_curve(&self, token_id: TokenId) -> Vec<PremiumPoint> {
+        let released_at = match self.internal_released_at(&normalize_token_id(&token_id)) {
+            Some(released_at) => released_at,
+            None => return vec![],
+        };
+
+        (0..=u64::from(self.release_auction.duration_days))
+            .map(|day| {
+                let timestamp = released_at + day * ONE_DAY_NANOSECOND;
+                PremiumPoint {
+                    timestamp: U64(timestamp),
+                    premium: U128(self.release_auction.premium_at(released_at, timestamp)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    //when the name leaves its grace period and becomes available to anyone
+    pub(crate) fn internal_released_at(&self, token_id: &TokenId) -> Option<u64> {
+        Some(self.internal_expires_at(token_id)? + GRACE_PERIOD_NANOSECOND)
+    }
+
+    pub(crate) fn internal_release_premium(&self, token_id: &TokenId) -> Balance {
+        if self.internal_token_status(token_id) != Some(NameStatus::Released) {
+            return 0;
+        }
+        let released_at = self.internal_released_at(token_id).unwrap();
+        self.release_auction.premium_at(released_at, env::block_timestamp())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    //give a name up. Everything stored for it is removed and it can be registered again straight away
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        let token_id = normalize_token_id(&token_id);
+        self.assert_token_owner(&token_id);
+
+        let token = self.internal_remove_token(&token_id);
+        self.internal_log_burn(token, &token_id, None, memo);
+    }
+
+    //clean up a name whose grace period has passed without waiting for someone to register it again.
+    //a released name is only removed once its release auction is over, since the premium is based on its expiry
+    #[payable]
+    pub fn reclaim_expired(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.assert_only_owner();
+        let domain = assert_valid_domain(&token_id);
+        let token_id = domain.to_string();
+        assert!(
+            self.internal_token_status(&token_id).expect("TOKEN_NOT_FOUND") == NameStatus::Released,
+            "NAME_NOT_RELEASED"
+        );
+        assert!(
+            domain.is_subdomain() || self.internal_release_premium(&token_id) == 0,
+            "RELEASE_AUCTION_ACTIVE"
+        );
+
+        let token = self.internal_release_token(&token_id);
+        self.internal_log_burn(token, &token_id, Some(env::predecessor_account_id()), Some("expired".to_string()));
+    }
+}
+
+impl Contract {
+    fn internal_log_burn(&self, token: Token, token_id: &TokenId, authorized_id: Option<AccountId>, memo: Option<String>) {
+        // Construct the burn log as per the events standard.
+        let nft_burn_log: EventLog = EventLog {
+            // Standard name ("nep171").
+            standard: NFT_STANDARD_NAME.to_string(),
+            // Version of the standard ("nft-1.0.0").
+            version: NFT_METADATA_SPEC.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                // Owner of the burned token.
+                owner_id: token.owner_id.to_string(),
+                // The optional account that burned the token on behalf of its owner.
+                authorized_id: authorized_id.map(|account_id| account_id.to_string()),
+                // Vector of token IDs that were burned.
+                token_ids: vec![token_id.to_string()],
+                // An optional memo to include.
+                memo,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_burn_log.to_string());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::*;
+
+/// Key a chain is registered and its addresses are recorded under, e.g. "Ethereum".
+pub type ChainKey = String;
+
+/// Longest key a chain can be registered under.
+pub const MAX_CHAIN_KEY_LENGTH: usize = 32;
+
+/// How addresses on a chain are written.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AddressFormat {
+    Evm,
+    Bitcoin,
+    Solana,
+    Substrate,
+    Cardano,
+    Tron,
+    Near,
+    Cosmos,
+    //any address is accepted as-is
+    Other,
+}
+
+/// A chain names can record addresses for.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Chain {
+    //SLIP-44 coin type ("slip44:60") or CAIP-2 chain id ("eip155:43114")
+    pub chain_id: String,
+    //human readable name, e.g. "Avalanche C-Chain"
+    pub name: String,
+    pub address_format: AddressFormat,
+    //a deprecated chain keeps resolving existing records but new addresses can't be added for it
+    pub deprecated: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonChain {
+    pub key: ChainKey,
+    #[serde(flatten)]
+    pub chain: Chain,
+}
+
+impl Network {
+    /// Chains of the former fixed `Network` enum, registered under their `to_string()` so
+    /// records stored before the registry existed keep resolving.
+    pub(crate) fn builtin_chains() -> Vec<(ChainKey, Chain)> {
+        [
+            (Network::Ethereum, "slip44:60", AddressFormat::Evm),
+            (Network::NEAR, "slip44:397", AddressFormat::Near),
+            (Network::Polkadot, "slip44:354", AddressFormat::Substrate),
+            (Network::Solana, "slip44:501", AddressFormat::Solana),
+            (Network::Terra, "slip44:330", AddressFormat::Cosmos),
+            (Network::Cardano, "slip44:1815", AddressFormat::Cardano),
+            (Network::Tron, "slip44:195", AddressFormat::Tron),
+            (Network::Bitcoin, "slip44:0", AddressFormat::Bitcoin),
+        ]
+        .into_iter()
+        .map(|(network, chain_id, address_format)| {
+            (network.to_string(), Chain {
+                chain_id: chain_id.to_string(),
+                name: network.to_string(),
+                address_format,
+                deprecated: false,
+            })
+        })
+        .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn add_chain(&mut self, key: ChainKey, chain: Chain) {
+        self.assert_only_owner();
+        assert!(
+            !key.is_empty() && key.len() <= MAX_CHAIN_KEY_LENGTH && !key.contains(':'),
+            "INVALID_CHAIN_KEY: must be between 1 and {} characters without ':'",
+            MAX_CHAIN_KEY_LENGTH
+        );
+        assert!(self.chains.get(&key).is_none(), "CHAIN_ALREADY_EXISTS");
+
+        self.chains.insert(&key, &chain);
+    }
+
+    pub fn deprecate_chain(&mut self, key: ChainKey) {
+        self.assert_only_owner();
+        let mut chain = self.chains.get(&key).expect("CHAIN_NOT_FOUND");
+
+        chain.deprecated = true;
+        self.chains.insert(&key, &chain);
+    }
+
+    pub fn get_chain(&self, key: ChainKey) -> Option<Chain> {
+        self.chains.get(&key)
+    }
+
+    pub fn get_chains(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonChain> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.chains.iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(key, chain)| JsonChain { key, chain })
+            .collect()
+    }
+}
+
+impl Contract {
+    //make sure new addresses can be recorded for the chain, returning its registry entry
+    pub(crate) fn assert_chain_active(&self, key: &ChainKey) -> Chain {
+        let chain = self.chains.get(key).unwrap_or_else(|| panic!("CHAIN_NOT_FOUND: {}", key));
+        assert!(!chain.deprecated, "CHAIN_DEPRECATED: {}", key);
+        chain
+    }
+
+    pub(crate) fn internal_register_builtin_chains(&mut self) {
+        for (key, chain) in Network::builtin_chains() {
+            if self.chains.get(&key).is_none() {
+                self.chains.insert(&key, &chain);
+            }
+        }
+    }
+}
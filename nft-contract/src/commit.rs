@@ -0,0 +1,156 @@
+use crate::*;
+
+//a commitment must wait this long before it can be revealed, so its name can't be front-run in the same block
+const MIN_COMMITMENT_AGE: u64 = 60_000_000_000;
+//after this long a commitment can no longer be revealed and its deposit can be refunded
+const MAX_COMMITMENT_AGE: u64 = ONE_DAY_NANOSECOND;
+
+/// A hidden registration request, stored under the committer's account and
+/// `sha256("{name}:{owner_id}:{secret}")`, along with the deposit that will pay for
+/// the registration once revealed. Keying by committer means copying someone else's
+/// hash from a pending transaction neither blocks nor claims their commitment.
+/// Registrations paid in a fungible token or in USD reveal their commitment too, and
+/// get its deposit back or put it towards the price.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Commitment {
+    pub deposit: Balance,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonCommitment {
+    pub commitment: Base64VecU8,
+    pub account_id: AccountId,
+    pub deposit: U128,
+    pub created_at: U64,
+    pub expires_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    //computes the commitment for a name. Calling this on an RPC node reveals the secret to it, so
+    //clients that care should compute sha256("{name}:{owner_id}:{secret}") locally instead
+    pub fn make_commitment(&self, name: TokenId, owner_id: AccountId, secret: String) -> Base64VecU8 {
+        let domain = assert_valid_domain(&name);
+        Base64VecU8(hash_commitment(&domain, &owner_id, &secret).to_vec())
+    }
+
+    //first phase of a registration: store the hidden commitment and the deposit that will pay for it
+    #[payable]
+    pub fn commit(&mut self, commitment: Base64VecU8) {
+        let deposit = env::attached_deposit();
+        let key = (env::predecessor_account_id(), commitment_hash(&commitment));
+
+        assert!(self.commitments.get(&key).is_none(), "COMMITMENT_EXISTS");
+
+        let initial_storage_usage = env::storage_usage();
+        self.commitments.insert(&key, &Commitment {
+            deposit,
+            created_at: env::block_timestamp(),
+        });
+        //the deposit is held until the commitment is removed, so it must at least pay for the entry meanwhile
+        let storage_cost = env::storage_byte_cost() * Balance::from(env::storage_usage() - initial_storage_usage);
+        assert!(deposit >= storage_cost, "COMMITMENT_REQUIRES_DEPOSIT: {} yoctoNEAR needed for storage", storage_cost);
+        self.internal_hold_user_funds(deposit);
+    }
+
+    //second phase of a registration: reveal the name behind the caller's commitment and register it to them.
+    //the committed deposit plus any attached deposit pays for the registration and the rest is refunded
+    #[payable]
+    pub fn reveal_and_register(
+        &mut self,
+        name: TokenId,
+        secret: String,
+        metadata: TokenMetadata,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        years: Option<u32>,
+        //partner credited with a share of the payment, see `set_referral_rate`
+        referrer: Option<AccountId>,
+    ) -> (u64, u128) {
+        let domain = assert_valid_domain(&name);
+        self.assert_not_in_presale(&domain);
+        let owner_id = env::predecessor_account_id();
+
+        let deposit = self.internal_take_commitment(&owner_id, &domain, &owner_id, &secret);
+        let payment = deposit + env::attached_deposit();
+
+        self.internal_paid_mint(&domain, metadata, owner_id, perpetual_royalties, years, referrer, payment, 0)
+    }
+
+    //the committer can take a commitment back with its deposit at any time, e.g. after its reveal failed
+    #[payable]
+    pub fn cancel_commitment(&mut self, commitment: Base64VecU8) -> U128 {
+        assert_one_yocto();
+        let key = (env::predecessor_account_id(), commitment_hash(&commitment));
+        let stored = self.commitments.remove(&key).expect("COMMITMENT_NOT_FOUND");
+
+        self.internal_release_user_funds(stored.deposit);
+        Promise::new(key.0).transfer(stored.deposit);
+
+        U128(stored.deposit)
+    }
+
+    //anyone can clear a commitment that was never revealed in time. The full deposit goes back to the committer
+    pub fn clear_expired_commitment(&mut self, account_id: AccountId, commitment: Base64VecU8) -> U128 {
+        let key = (account_id, commitment_hash(&commitment));
+        let stored = self.commitments.get(&key).expect("COMMITMENT_NOT_FOUND");
+        assert!(
+            env::block_timestamp() > stored.created_at + MAX_COMMITMENT_AGE,
+            "COMMITMENT_NOT_EXPIRED"
+        );
+
+        self.commitments.remove(&key);
+        self.internal_release_user_funds(stored.deposit);
+        Promise::new(key.0).transfer(stored.deposit);
+
+        U128(stored.deposit)
+    }
+
+    pub fn get_commitments(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonCommitment> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.commitments.iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|((account_id, commitment), stored)| JsonCommitment {
+                commitment: Base64VecU8(commitment.to_vec()),
+                account_id,
+                deposit: U128(stored.deposit),
+                created_at: U64(stored.created_at),
+                expires_at: U64(stored.created_at + MAX_COMMITMENT_AGE),
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    //removes the commitment `committer` made to registering the name to `owner_id`, as long as it can be
+    //revealed now, and returns its deposit
+    pub(crate) fn internal_take_commitment(
+        &mut self,
+        committer: &AccountId,
+        domain: &DomainName,
+        owner_id: &AccountId,
+        secret: &str,
+    ) -> Balance {
+        let key = (committer.clone(), hash_commitment(domain, owner_id, secret));
+        let stored = self.commitments.remove(&key).expect("COMMITMENT_NOT_FOUND");
+        let now = env::block_timestamp();
+        assert!(now >= stored.created_at + MIN_COMMITMENT_AGE, "COMMITMENT_TOO_NEW");
+        assert!(now <= stored.created_at + MAX_COMMITMENT_AGE, "COMMITMENT_EXPIRED");
+
+        self.internal_release_user_funds(stored.deposit);
+        stored.deposit
+    }
+}
+
+pub(crate) fn hash_commitment(domain: &DomainName, owner_id: &AccountId, secret: &str) -> CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(format!("{}:{}:{}", domain, owner_id, secret).as_bytes()));
+    hash
+}
+
+fn commitment_hash(commitment: &Base64VecU8) -> CryptoHash {
+    CryptoHash::try_from(commitment.0.as_slice()).expect("INVALID_COMMITMENT")
+}
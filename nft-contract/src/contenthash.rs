@@ -0,0 +1,330 @@
+use crate::*;
+use near_sdk::base64;
+use near_sdk::bs58;
+
+//multicodec prefixes of the binary contenthash format (ENSIP-7), as unsigned varints
+const IPFS_NS: &[u8] = &[0xe3, 0x01];
+const SWARM_NS: &[u8] = &[0xe4, 0x01];
+const IPNS_NS: &[u8] = &[0xe5, 0x01];
+const ARWEAVE_NS: &[u8] = &[0x90, 0xb2, 0xca, 0x05];
+
+const CID_V1: u8 = 0x01;
+const DAG_PB: u64 = 0x70;
+const LIBP2P_KEY: u64 = 0x72;
+//sha2-256 multihash header, the only hash a CIDv0 can hold
+const SHA2_256_MULTIHASH: &[u8] = &[0x12, 0x20];
+//CIDv1 header of a swarm manifest addressed by a keccak-256 hash
+const SWARM_MANIFEST_CID: &[u8] = &[0x01, 0xfa, 0x01, 0x1b, 0x20];
+
+const BASE32_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE36_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Content a name points to, such as a website. Stored in the binary contenthash format
+/// so IPFS CIDs are returned as base32 CIDv1 ("bafy...") whichever version was set,
+/// IPNS names as base36 CIDv1 ("k51..."), Arweave transaction ids as base64url and
+/// Swarm hashes as hex.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ContentHash {
+    //CIDv0 ("Qm...") or base32 CIDv1 ("b...")
+    Ipfs(String),
+    //libp2p-key CIDv1 in base36 ("k...") or base32 ("b...")
+    Ipns(String),
+    //43 character base64url transaction id
+    Arweave(String),
+    //64 character hex manifest hash
+    Swarm(String),
+}
+
+impl ContentHash {
+    /// Validates the content pointer and returns its binary form.
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        match self {
+            ContentHash::Ipfs(cid) => {
+                let cid = if cid.starts_with("Qm") {
+                    let multihash = bs58::decode(cid).into_vec().map_err(|_| "invalid base58 CIDv0")?;
+                    if multihash.len() != 34 || !multihash.starts_with(SHA2_256_MULTIHASH) {
+                        return Err("CIDv0 must be a sha2-256 multihash".to_string());
+                    }
+                    [&[CID_V1, DAG_PB as u8], multihash.as_slice()].concat()
+                } else {
+                    decode_multibase_cid(cid)?
+                };
+                validate_cid(&cid, None)?;
+                Ok([IPFS_NS, cid.as_slice()].concat())
+            }
+            ContentHash::Ipns(cid) => {
+                let cid = decode_multibase_cid(cid)?;
+                validate_cid(&cid, Some(LIBP2P_KEY))?;
+                Ok([IPNS_NS, cid.as_slice()].concat())
+            }
+            ContentHash::Arweave(tx_id) => {
+                let hash = base64::decode_config(tx_id, base64::URL_SAFE_NO_PAD)
+                    .ok()
+                    .filter(|hash| hash.len() == 32)
+                    .ok_or("Arweave transaction id must be 32 bytes in base64url")?;
+                Ok([ARWEAVE_NS, hash.as_slice()].concat())
+            }
+            ContentHash::Swarm(hash) => {
+                let hash = decode_hex(hash)
+                    .filter(|hash| hash.len() == 32)
+                    .ok_or("Swarm hash must be 64 hex characters")?;
+                Ok([SWARM_NS, SWARM_MANIFEST_CID, hash.as_slice()].concat())
+            }
+        }
+    }
+
+    /// Gateway URI of the content, e.g. "ipfs://bafy..." or "ar://...".
+    pub fn to_uri(&self) -> String {
+        match self {
+            ContentHash::Ipfs(cid) => format!("ipfs://{}", cid),
+            ContentHash::Ipns(cid) => format!("ipns://{}", cid),
+            ContentHash::Arweave(tx_id) => format!("ar://{}", tx_id),
+            ContentHash::Swarm(hash) => format!("bzz://{}", hash),
+        }
+    }
+
+    /// Reads back a content pointer stored by `encode`.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if let Some(cid) = bytes.strip_prefix(IPFS_NS) {
+            Some(ContentHash::Ipfs(format!("b{}", encode_base32(cid))))
+        } else if let Some(cid) = bytes.strip_prefix(IPNS_NS) {
+            Some(ContentHash::Ipns(format!("k{}", encode_base36(cid))))
+        } else if let Some(hash) = bytes.strip_prefix(ARWEAVE_NS) {
+            Some(ContentHash::Arweave(base64::encode_config(hash, base64::URL_SAFE_NO_PAD)))
+        } else {
+            let hash = bytes.strip_prefix(SWARM_NS)?.strip_prefix(SWARM_MANIFEST_CID)?;
+            Some(ContentHash::Swarm(hash.iter().map(|b| format!("{:02x}", b)).collect()))
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_contenthash(&mut self, token_id: TokenId, contenthash: ContentHash) {
+        let initial_storage_usage = env::storage_usage();
+        // assert if function caller is not the owner or manager
+        self.assert_token_owner_or_manager(&token_id);
+
+        self.assert_token_expires(&token_id);
+
+        let encoded = contenthash
+            .encode()
+            .unwrap_or_else(|reason| panic!("INVALID_CONTENTHASH: {}", reason));
+        self.contenthash_by_token_id.insert(&token_id, &encoded);
+        log_name_service_event(EventLogVariant::ContenthashChanged(vec![ContenthashChangedLog {
+            token_id: token_id.clone(),
+            contenthash: Some(contenthash.to_uri()),
+        }]));
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+
+    pub fn get_contenthash(&self, token_id: TokenId) -> Option<ContentHash> {
+        let token_id = normalize_token_id(&token_id);
+        if self.is_token_expires(&token_id) {
+            return None;
+        }
+
+        self.contenthash_by_token_id
+            .get(&token_id)
+            .and_then(|encoded| ContentHash::decode(&encoded))
+    }
+
+    #[payable]
+    pub fn remove_contenthash(&mut self, token_id: TokenId) {
+        let initial_storage_usage = env::storage_usage();
+        assert_one_yocto();
+        if self.is_token_expires(&token_id) {
+            self.assert_only_owner();
+        } else {
+            self.assert_token_owner_or_manager(&token_id);
+        }
+
+        self.contenthash_by_token_id.remove(&token_id).expect("NO_CONTENTHASH_TOKEN");
+        log_name_service_event(EventLogVariant::ContenthashChanged(vec![ContenthashChangedLog {
+            token_id: token_id.clone(),
+            contenthash: None,
+        }]));
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+}
+
+//CIDv1 in base32 ("b...") or base36 ("k...") multibase
+fn decode_multibase_cid(cid: &str) -> Result<Vec<u8>, String> {
+    let decoded = match cid.split_at(cid.len().min(1)) {
+        ("b", rest) => decode_base32(rest),
+        ("k", rest) => decode_base36(rest),
+        _ => None,
+    };
+    decoded.ok_or_else(|| "CIDv1 must be base32 (b...) or base36 (k...)".to_string())
+}
+
+//CIDv1 bytes: version, content codec and a multihash whose length matches its digest
+fn validate_cid(cid: &[u8], expected_codec: Option<u64>) -> Result<(), String> {
+    let rest = cid.strip_prefix(&[CID_V1]).ok_or("unsupported CID version")?;
+    let (codec, rest) = read_varint(rest).ok_or("invalid CID codec")?;
+    if expected_codec.is_some_and(|expected| expected != codec) {
+        return Err("unexpected CID codec".to_string());
+    }
+
+    let (_, rest) = read_varint(rest).ok_or("invalid multihash")?;
+    let (length, digest) = read_varint(rest).ok_or("invalid multihash")?;
+    if length != digest.len() as u64 || digest.is_empty() {
+        return Err("multihash length mismatch".to_string());
+    }
+    Ok(())
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
+
+//unpadded lowercase RFC 4648 base32
+fn decode_base32(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|&a| a == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            text.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        text.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    text
+}
+
+//lowercase base36, where each leading '0' stands for a leading zero byte
+fn decode_base36(text: &str) -> Option<Vec<u8>> {
+    let zeros = text.bytes().take_while(|&c| c == b'0').count();
+    //little-endian base 256 digits
+    let mut digits: Vec<u8> = Vec::new();
+    for c in text.bytes().skip(zeros) {
+        let mut carry = BASE36_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) * 36;
+            *digit = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            digits.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut bytes = vec![0; zeros];
+    bytes.extend(digits.iter().rev());
+    Some(bytes)
+}
+
+fn encode_base36(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    //little-endian base 36 digits
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes[zeros..] {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 36) as u8;
+            carry /= 36;
+        }
+        while carry > 0 {
+            digits.push((carry % 36) as u8);
+            carry /= 36;
+        }
+    }
+    let mut text = "0".repeat(zeros);
+    text.extend(digits.iter().rev().map(|&d| BASE36_ALPHABET[usize::from(d)] as char));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn ipfs_cid_v0_and_v1_encode_the_same() {
+        let v0 = ContentHash::Ipfs("QmRAQB6YaCyidP37UdDnjFY5vQuiBrcqdyoW1CuDgwxkD4".to_string());
+        let encoded = v0.encode().unwrap();
+        assert_eq!(
+            to_hex(&encoded),
+            "e3010170122029f2d17be6139079dc48696d1f582a8530eb9805b561eda517e22a892c7e3f1f"
+        );
+
+        let v1 = ContentHash::decode(&encoded).unwrap();
+        assert_eq!(v1, ContentHash::Ipfs("bafybeibj6lixxzqtsb45ysdjnupvqkufgdvzqbnvmhw2kf7cfkesy7r7d4".to_string()));
+        assert_eq!(v1.encode().unwrap(), encoded);
+    }
+
+    #[test]
+    fn ipns_arweave_and_swarm_round_trip() {
+        for contenthash in [
+            ContentHash::Ipns("k51qzi5uqu5dlvj2baxnqndepeb86cbk3ng7n3i46uzyxzyqj2xjonzllnv0v8".to_string()),
+            ContentHash::Arweave("ys32Pt8uC7TrVxHdOLByOspfPEq2LO63wREHQIM9SJQ".to_string()),
+            ContentHash::Swarm("d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea162".to_string()),
+        ] {
+            let encoded = contenthash.encode().unwrap();
+            assert_eq!(ContentHash::decode(&encoded), Some(contenthash));
+        }
+    }
+
+    #[test]
+    fn invalid_contenthashes() {
+        for contenthash in [
+            //truncated CIDv0
+            ContentHash::Ipfs("QmRAQB6YaCyidP37UdDnjFY5vQuiBrcqdyoW1CuDgwxk".to_string()),
+            ContentHash::Ipfs("zdj7WWeQ43G6JJvLWQWZpyHuAMq6uYWRjkBXFad11vE2LHhQ7".to_string()),
+            //an IPFS CID is not an IPNS key
+            ContentHash::Ipns("bafybeibj6lixxzqtsb45ysdjnupvqkufgdvzqbnvmhw2kf7cfkesy7r7d4".to_string()),
+            ContentHash::Arweave("ys32Pt8uC7TrVxHdOLByOspfPEq2LO63wREHQIM9S".to_string()),
+            ContentHash::Swarm("d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea1".to_string()),
+            ContentHash::Swarm("zz".repeat(32)),
+        ] {
+            assert!(contenthash.encode().is_err(), "{:?}", contenthash);
+        }
+    }
+}
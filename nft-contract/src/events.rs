@@ -3,7 +3,8 @@ use std::fmt;
 use near_sdk::serde::{Deserialize, Serialize};
 
 /// Enum that represents the data type of the EventLog.
-/// The enum can either be an NftMint or an NftTransfer.
+/// NftMint, NftTransfer and NftBurn are NEP-171 events, the others belong to the name service
+/// standard and describe record changes, renewals, price changes and releases.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "event", content = "data")]
 #[serde(rename_all = "snake_case")]
@@ -12,6 +13,18 @@ use near_sdk::serde::{Deserialize, Serialize};
 pub enum EventLogVariant {
     NftMint(Vec<NftMintLog>),
     NftTransfer(Vec<NftTransferLog>),
+    NftBurn(Vec<NftBurnLog>),
+    AddrSet(Vec<AddrSetLog>),
+    AddrRemoved(Vec<AddrRemovedLog>),
+    RecordsReset(Vec<RecordsResetLog>),
+    TextSet(Vec<TextSetLog>),
+    TextRemoved(Vec<TextRemovedLog>),
+    ContenthashChanged(Vec<ContenthashChangedLog>),
+    ResolverChanged(Vec<ResolverChangedLog>),
+    ManagerChanged(Vec<ManagerChangedLog>),
+    NameRenewed(Vec<NameRenewedLog>),
+    PriceChanged(Vec<PriceChangedLog>),
+    NameReleased(Vec<NameReleasedLog>),
 }
 
 /// Interface to capture data about an event
@@ -78,6 +91,182 @@ pub struct NftTransferLog {
     pub memo: Option<String>,
 }
 
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of the burned tokens, "owner.near"
+/// * `authorized_id`: account that burned the tokens on behalf of the owner
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurnLog {
+    pub owner_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<String>,
+
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture an address being recorded for a name
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `network`: chain the address is recorded for, e.g. "Ethereum"
+/// * `address`: "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AddrSetLog {
+    pub token_id: String,
+    pub network: String,
+    pub address: String,
+}
+
+/// An event log to capture an address being removed from a name
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `network`: "Ethereum"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AddrRemovedLog {
+    pub token_id: String,
+    pub network: String,
+}
+
+/// An event log to capture every record of a name being cleared when it changed hands,
+/// or only its addresses with `reset_token_addresses`
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `epoch`: record epoch the name starts with, e.g. "2"
+/// * `record_type`: "addr" when only the addresses were cleared, absent when every record was
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecordsResetLog {
+    pub token_id: String,
+    pub epoch: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_type: Option<String>,
+}
+
+/// An event log to capture a text record being set for a name
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `key`: "com.twitter"
+/// * `value`: "manhng"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TextSetLog {
+    pub token_id: String,
+    pub key: String,
+    pub value: String,
+}
+
+/// An event log to capture a text record being removed from a name
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `key`: "com.twitter"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TextRemovedLog {
+    pub token_id: String,
+    pub key: String,
+}
+
+/// An event log to capture the website of a name changing
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `contenthash`: gateway URI, e.g. "ipfs://bafy...", absent when it was removed
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContenthashChangedLog {
+    pub token_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contenthash: Option<String>,
+}
+
+/// An event log to capture a name being pointed to a custom resolver contract
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `resolver_id`: "resolver.near", absent when the name went back to the records stored here
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResolverChangedLog {
+    pub token_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolver_id: Option<String>,
+}
+
+/// An event log to capture the manager of a name changing
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `manager_id`: "hot.near", absent when the manager was revoked
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ManagerChangedLog {
+    pub token_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manager_id: Option<String>,
+}
+
+/// An event log to capture a name's registration being extended
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `owner_id`: "manhng.near"
+/// * `expires_at`: new expiry in nanoseconds, e.g. "1700000000000000000"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NameRenewedLog {
+    pub token_id: String,
+    pub owner_id: String,
+    pub expires_at: String,
+}
+
+/// An event log to capture a registration price change
+///
+/// Arguments
+/// * `kind`: "tiers" for the contract-wide tiers, "tld" for the tiers of a TLD or "premium" for a premium name
+/// * `target`: TLD or premium name the change applies to
+/// * `price_per_year`: new yearly price of a premium name in yoctoNEAR, absent when it was removed
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceChangedLog {
+    pub kind: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_per_year: Option<String>,
+}
+
+/// An event log to capture an expired name being cleared so it can be registered again
+///
+/// Arguments
+/// * `token_id`: "manhng.btc"
+/// * `owner_id`: owner of the expired registration, "manhng.near"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NameReleasedLog {
+    pub token_id: String,
+    pub owner_id: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,4 +324,213 @@ mod tests {
         };
         assert_eq!(expected, log.to_string());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn nep_format_burn() {
+        let expected = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"user1.near","authorized_id":"owner.near","token_ids":["manhng.btc"],"memo":"expired"}]}"#;
+        let log = EventLog {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                owner_id: "user1.near".to_string(),
+                authorized_id: Some("owner.near".to_string()),
+                token_ids: vec!["manhng.btc".to_string()],
+                memo: Some("expired".to_string()),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_addr_set() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"addr_set","data":[{"token_id":"manhng.btc","network":"Ethereum","address":"0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::AddrSet(vec![AddrSetLog {
+                token_id: "manhng.btc".to_string(),
+                network: "Ethereum".to_string(),
+                address: "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string(),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_addr_removed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"addr_removed","data":[{"token_id":"manhng.btc","network":"Ethereum"},{"token_id":"manhng.btc","network":"NEAR"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::AddrRemoved(vec![
+                AddrRemovedLog {
+                    token_id: "manhng.btc".to_string(),
+                    network: "Ethereum".to_string(),
+                },
+                AddrRemovedLog {
+                    token_id: "manhng.btc".to_string(),
+                    network: "NEAR".to_string(),
+                },
+            ]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_records_reset() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"records_reset","data":[{"token_id":"manhng.btc","epoch":"2"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::RecordsReset(vec![RecordsResetLog {
+                token_id: "manhng.btc".to_string(),
+                epoch: "2".to_string(),
+                record_type: None,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_addr_reset() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"records_reset","data":[{"token_id":"manhng.btc","epoch":"1","record_type":"addr"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::RecordsReset(vec![RecordsResetLog {
+                token_id: "manhng.btc".to_string(),
+                epoch: "1".to_string(),
+                record_type: Some("addr".to_string()),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_text_set() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"text_set","data":[{"token_id":"manhng.btc","key":"com.twitter","value":"manhng"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::TextSet(vec![TextSetLog {
+                token_id: "manhng.btc".to_string(),
+                key: "com.twitter".to_string(),
+                value: "manhng".to_string(),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_text_removed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"text_removed","data":[{"token_id":"manhng.btc","key":"com.twitter"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::TextRemoved(vec![TextRemovedLog {
+                token_id: "manhng.btc".to_string(),
+                key: "com.twitter".to_string(),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_contenthash_changed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"contenthash_changed","data":[{"token_id":"manhng.btc","contenthash":"ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"},{"token_id":"manhnv.btc"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::ContenthashChanged(vec![
+                ContenthashChangedLog {
+                    token_id: "manhng.btc".to_string(),
+                    contenthash: Some("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string()),
+                },
+                ContenthashChangedLog {
+                    token_id: "manhnv.btc".to_string(),
+                    contenthash: None,
+                },
+            ]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_resolver_changed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"resolver_changed","data":[{"token_id":"manhng.btc","resolver_id":"resolver.near"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::ResolverChanged(vec![ResolverChangedLog {
+                token_id: "manhng.btc".to_string(),
+                resolver_id: Some("resolver.near".to_string()),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_manager_changed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"manager_changed","data":[{"token_id":"manhng.btc"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::ManagerChanged(vec![ManagerChangedLog {
+                token_id: "manhng.btc".to_string(),
+                manager_id: None,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_name_renewed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"name_renewed","data":[{"token_id":"manhng.btc","owner_id":"manhng.near","expires_at":"1700000000000000000"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NameRenewed(vec![NameRenewedLog {
+                token_id: "manhng.btc".to_string(),
+                owner_id: "manhng.near".to_string(),
+                expires_at: "1700000000000000000".to_string(),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_price_changed() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"price_changed","data":[{"kind":"tiers"},{"kind":"premium","target":"gold.btc","price_per_year":"5000000000000000000000000"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::PriceChanged(vec![
+                PriceChangedLog {
+                    kind: "tiers".to_string(),
+                    target: None,
+                    price_per_year: None,
+                },
+                PriceChangedLog {
+                    kind: "premium".to_string(),
+                    target: Some("gold.btc".to_string()),
+                    price_per_year: Some("5000000000000000000000000".to_string()),
+                },
+            ]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+
+    #[test]
+    fn nep_format_name_released() {
+        let expected = r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"name_released","data":[{"token_id":"manhng.btc","owner_id":"manhng.near"}]}"#;
+        let log = EventLog {
+            standard: "dnet_name_service".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::NameReleased(vec![NameReleasedLog {
+                token_id: "manhng.btc".to_string(),
+                owner_id: "manhng.near".to_string(),
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_FT_WITHDRAW_CALLBACK: Gas = Gas(5_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+
+/// What a payment sent with `ft_transfer_call` is for, given as its JSON `msg`, e.g.
+/// `{"action": "mint", "token_id": "manhng.btc", "metadata": {...}, "secret": "..."}` or
+/// `{"action": "renew", "token_id": "manhng.btc"}`. These payments earn no referral rewards,
+/// which are paid in NEAR, and names under a TLD with its own tiers are priced in NEAR only.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FtPaymentMsg {
+    Mint(Box<FtMint>),
+    //renews for as many whole years as the amount covers
+    Renew {
+        token_id: TokenId,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMint {
+    pub token_id: TokenId,
+    pub metadata: TokenMetadata,
+    //defaults to the sender of the tokens
+    pub receiver_id: Option<AccountId>,
+    //number of years to register for. If not given, as many whole years as the amount covers
+    pub years: Option<u32>,
+    //secret of the sender's commitment to the name, see `commit`. Its deposit is refunded
+    pub secret: String,
+}
+
+/// Payments received in one fungible token, in the token's own units.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct FtRevenue {
+    pub accrued: Balance,
+    pub withdrawn: Balance,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonFtRevenue {
+    pub token_account_id: AccountId,
+    pub total: U128,
+    pub withdrawn: U128,
+    pub available: U128,
+}
+
+//NEP-141 receiver interface
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_ft)]
+trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_ft_withdraw_callback)]
+trait FtWithdrawCallback {
+    fn on_ft_withdraw(&mut self, token_account_id: AccountId, amount: U128);
+}
+
+pub trait FtWithdrawCallback {
+    fn on_ft_withdraw(&mut self, token_account_id: AccountId, amount: U128);
+}
+
+#[near_bindgen]
+impl Contract {
+    //accept payments in a fungible token, priced by label length in the token's own units
+    pub fn set_ft_price_tiers(&mut self, token_account_id: AccountId, price_tiers: PriceTiers) {
+        self.assert_only_owner();
+        for price in [price_tiers.one_two_chars, price_tiers.three_chars, price_tiers.four_chars, price_tiers.five_plus_chars] {
+            assert!(price.0 > 0, "INVALID_FT_PRICE");
+        }
+        self.ft_price_tiers.insert(&token_account_id, &price_tiers);
+        log_price_changed("ft_tiers", Some(token_account_id.to_string()), None);
+    }
+
+    //stop accepting a fungible token. What was already received can still be withdrawn
+    pub fn remove_ft(&mut self, token_account_id: AccountId) {
+        self.assert_only_owner();
+        if self.ft_price_tiers.remove(&token_account_id).is_some() {
+            log_price_changed("ft_tiers", Some(token_account_id.to_string()), None);
+        }
+    }
+
+    pub fn get_accepted_fts(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<(AccountId, PriceTiers)> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.ft_price_tiers.iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+
+    pub fn get_ft_revenue(&self, token_account_id: AccountId) -> JsonFtRevenue {
+        let revenue = self.ft_revenue.get(&token_account_id).unwrap_or_default();
+        JsonFtRevenue {
+            token_account_id,
+            total: U128(revenue.accrued),
+            withdrawn: U128(revenue.withdrawn),
+            available: U128(revenue.accrued - revenue.withdrawn),
+        }
+    }
+
+    //the receiver must be registered with the token contract, otherwise the transfer fails and is rolled back
+    #[payable]
+    pub fn withdraw_ft(&mut self, token_account_id: AccountId, receiver_id: AccountId, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_only_owner();
+
+        let mut revenue = self.ft_revenue.get(&token_account_id).unwrap_or_default();
+        assert!(amount.0 <= revenue.accrued - revenue.withdrawn, "WITHDRAW_EXCEEDS_AVAILABLE_REVENUE");
+        revenue.withdrawn += amount.0;
+        self.ft_revenue.insert(&token_account_id, &revenue);
+
+        ext_ft::ft_transfer(
+            receiver_id,
+            amount,
+            None,
+            token_account_id.clone(), //contract account to make the call to
+            1, //ft_transfer requires one yocto
+            GAS_FOR_FT_TRANSFER, //attached GAS
+        )
+        .then(ext_ft_withdraw_callback::on_ft_withdraw(
+            token_account_id,
+            amount,
+            env::current_account_id(), //contract account to make the call to
+            NO_DEPOSIT, //attached deposit
+            GAS_FOR_FT_WITHDRAW_CALLBACK, //GAS attached to the call
+        ))
+    }
+}
+
+impl Contract {
+    //registers a name paid in a fungible token, returning the part of the amount spent
+    fn internal_ft_mint(&mut self, ft_tiers: &PriceTiers, sender_id: AccountId, amount: Balance, mint: FtMint) -> Balance {
+        let domain = assert_valid_domain(&mint.token_id);
+        self.assert_not_in_presale(&domain);
+        assert!(!domain.is_subdomain(), "INVALID_NAME: subdomains cannot be minted directly");
+        self.assert_tld_open(&domain);
+        //premium names and the release auction are priced in NEAR only
+        assert!(self.premium_prices.get(&domain.to_string()).is_none(), "PREMIUM_NAME_NEAR_ONLY");
+        assert!(self.internal_release_premium(&domain.to_string()) == 0, "RELEASE_AUCTION_NEAR_ONLY");
+        assert!(!self.internal_has_tld_prices(&domain), "TLD_PRICED_IN_NEAR_ONLY: {}", domain.tld);
+
+        let price_per_year = ft_tiers.price_for_label(domain.name_label());
+        let years = match mint.years {
+            Some(years) => {
+                assert!(years > 0, "MINT_AT_LEAST_ONE_YEAR");
+                u64::from(years)
+            }
+            None => u64::try_from(amount / price_per_year).ok().unwrap(),
+        };
+        assert!(years > 0, "INVALID_DEPOSIT");
+        let price = price_per_year * Balance::from(years);
+        assert!(amount >= price, "INVALID_DEPOSIT");
+
+        let receiver_id = mint.receiver_id.unwrap_or_else(|| sender_id.clone());
+        let deposit = self.internal_take_commitment(&sender_id, &domain, &receiver_id, &mint.secret);
+        self.internal_mint(&domain, mint.metadata, receiver_id, None, years);
+        Promise::new(sender_id).transfer(deposit);
+        price
+    }
+
+    //renews a name paid in a fungible token, returning the part of the amount spent
+    fn internal_ft_renew(&mut self, ft_tiers: &PriceTiers, sender_id: AccountId, amount: Balance, token_id: TokenId) -> Balance {
+        let domain = assert_valid_domain(&token_id);
+        self.assert_renewable(&domain, &sender_id);
+        assert!(self.premium_prices.get(&domain.to_string()).is_none(), "PREMIUM_NAME_NEAR_ONLY");
+        assert!(!self.internal_has_tld_prices(&domain), "TLD_PRICED_IN_NEAR_ONLY: {}", domain.tld);
+        let token_id = domain.to_string();
+
+        let price_per_year = ft_tiers.price_for_label(domain.name_label());
+        let years = u64::try_from(amount / price_per_year).ok().unwrap();
+        assert!(years > 0, "EXTEND_AT_LEAST_ONE_YEAR");
+
+        let expires_at = self.internal_extend_token(&token_id, years * ONE_YEAR_NANOSECOND);
+        self.internal_log_name_renewed(&token_id, expires_at);
+        price_per_year * Balance::from(years)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    //called by an accepted token contract on `ft_transfer_call`. Any failure panics, which makes the
+    //token contract refund the whole amount, and whatever the registration didn't use is returned
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let token_account_id = env::predecessor_account_id();
+        let ft_tiers = self.ft_price_tiers.get(&token_account_id).expect("FT_NOT_ACCEPTED");
+        let payment: FtPaymentMsg = near_sdk::serde_json::from_str(&msg).expect("INVALID_FT_MSG");
+
+        let price = match payment {
+            FtPaymentMsg::Mint(mint) => self.internal_ft_mint(&ft_tiers, sender_id, amount.0, *mint),
+            FtPaymentMsg::Renew { token_id } => self.internal_ft_renew(&ft_tiers, sender_id, amount.0, token_id),
+        };
+
+        let mut revenue = self.ft_revenue.get(&token_account_id).unwrap_or_default();
+        revenue.accrued += price;
+        self.ft_revenue.insert(&token_account_id, &revenue);
+
+        PromiseOrValue::Value(U128(amount.0 - price))
+    }
+}
+
+#[near_bindgen]
+impl FtWithdrawCallback for Contract {
+    //a failed transfer leaves the tokens with this contract, so they can be withdrawn again
+    #[private]
+    fn on_ft_withdraw(&mut self, token_account_id: AccountId, amount: U128) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+        let mut revenue = self.ft_revenue.get(&token_account_id).unwrap_or_default();
+        revenue.withdrawn -= amount.0;
+        self.ft_revenue.insert(&token_account_id, &revenue);
+    }
+}
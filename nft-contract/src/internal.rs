@@ -62,30 +62,16 @@ pub(crate) fn assert_at_least_one_yocto() {
     )
 }
 
-//refund the initial deposit based on the amount of storage that was used up
-pub(crate) fn refund_deposit(storage_used: u64) {
-    //get how much it would cost to store the information
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-    //get the attached deposit
-    let attached_deposit = env::attached_deposit();
-
-    //make sure that the attached deposit is greater than or equal to the required cost
-    assert!(
-        required_cost <= attached_deposit,
-        "Must attach {} yoctoNEAR to cover storage",
-        required_cost,
-    );
-
-    //get the refund amount from the attached deposit - required cost
-    let refund = attached_deposit - required_cost;
-
-    //if the refund is greater than 1 yocto NEAR, we refund the predecessor that amount
-    if refund > 1 {
-        Promise::new(env::predecessor_account_id()).transfer(refund);
-    }
+//logs an event of the name service standard
+pub(crate) fn log_name_service_event(event: EventLogVariant) {
+    let log = EventLog {
+        standard: NAME_SERVICE_STANDARD_NAME.to_string(),
+        version: NAME_SERVICE_EVENT_VERSION.to_string(),
+        event,
+    };
+    env::log_str(&log.to_string());
 }
 
-
 impl Contract {
     //add a token to the set of tokens an owner has
     pub(crate) fn internal_add_token_to_owner(
@@ -198,6 +184,10 @@ impl Contract {
         };
         //insert that new token into the tokens_by_id, replacing the old entry 
         self.tokens_by_id.insert(token_id, &new_token);
+        //the manager, the primary names and, unless carried over, the records were chosen by the previous owner
+        self.managers_by_token_id.remove(token_id);
+        self.internal_remove_primary_names(token_id);
+        self.internal_records_on_transfer(token_id);
 
         //if there was some memo attached, we log it. 
         if let Some(memo) = memo.as_ref() {
@@ -239,6 +229,34 @@ impl Contract {
         token
     }
 
+    //removes a token and everything stored for it, refunding the owner for the approvals storage
+    pub(crate) fn internal_remove_token(&mut self, token_id: &TokenId) -> Token {
+        let token = self.tokens_by_id.remove(token_id).expect("No token");
+
+        self.internal_remove_token_from_owner(&token.owner_id, token_id);
+        self.token_metadata_by_id.remove(token_id);
+        self.internal_remove_primary_names(token_id);
+        self.internal_reset_records(token_id);
+        //a name registered again starts over from the first epoch. Its subdomains are orphaned, see `internal_is_orphaned`
+        self.record_epochs.remove(token_id);
+        self.managers_by_token_id.remove(token_id);
+        self.internal_update_tld_registrations(token_id, false);
+
+        refund_approved_account_ids(token.owner_id.clone(), &token.approved_account_ids);
+
+        token
+    }
+
+    //removes an expired token so its name can be registered again
+    pub(crate) fn internal_release_token(&mut self, token_id: &TokenId) -> Token {
+        let token = self.internal_remove_token(token_id);
+        log_name_service_event(EventLogVariant::NameReleased(vec![NameReleasedLog {
+            token_id: token_id.to_string(),
+            owner_id: token.owner_id.to_string(),
+        }]));
+        token
+    }
+
     pub(crate) fn assert_only_owner(&self) {
         let account_id = env::predecessor_account_id();
         assert_owner(&self.owner_id, &account_id)
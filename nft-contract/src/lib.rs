@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     env, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, PromiseOrValue,
@@ -20,6 +20,21 @@ pub use crate::address::*;
 pub use crate::ttl::*;
 pub use crate::price::*;
 pub use crate::storage_manage::*;
+pub use crate::name::*;
+pub use crate::auction::*;
+pub use crate::commit::*;
+pub use crate::tld::*;
+pub use crate::reverse::*;
+pub use crate::text::*;
+pub use crate::chain::*;
+pub use crate::contenthash::*;
+pub use crate::resolver::*;
+pub use crate::records::*;
+pub use crate::referral::*;
+pub use crate::revenue::*;
+pub use crate::presale::*;
+pub use crate::ft_payment::*;
+pub use crate::usd_price::*;
 
 mod internal;
 mod approval; 
@@ -34,14 +49,41 @@ mod utils;
 mod ttl;
 mod price;
 mod storage_manage;
+mod name;
+mod auction;
+mod commit;
+mod subdomain;
+mod tld;
+mod reverse;
+mod text;
+mod chain;
+mod address_format;
+mod contenthash;
+mod resolver;
+mod manager;
+mod records;
+mod burn;
+mod referral;
+mod revenue;
+mod presale;
+mod ft_payment;
+mod usd_price;
 
 /// This spec can be treated like a version of the standard.
 pub const NFT_METADATA_SPEC: &str = "nft-1.0.0";
 /// This is the name of the NFT standard we're using
 pub const NFT_STANDARD_NAME: &str = "nep171";
+/// Standard name and version of the events describing record changes, renewals, prices and releases
+pub const NAME_SERVICE_STANDARD_NAME: &str = "dnet_name_service";
+pub const NAME_SERVICE_EVENT_VERSION: &str = "1.0.0";
 
 const ONE_YEAR_NANOSECOND: u64 = 31_536_000_000_000_000u64;
 
+const ONE_DAY_NANOSECOND: u64 = 86_400_000_000_000u64;
+
+//how long after expiry only the previous owner can renew a name
+const GRACE_PERIOD_NANOSECOND: u64 = 90 * ONE_DAY_NANOSECOND;
+
 const ONE_NEAR_ES_YOCTO: Balance = 1_000_000_000_000_000_000_000_000;
 
 const DEFAULT_PRICE_PER_YEAR: Balance = ONE_NEAR_ES_YOCTO / 5;
@@ -67,9 +109,82 @@ pub struct Contract {
     //keeps track of token address
     pub addresses_by_token_id : UnorderedMap<TokenId, HashMap<String, String>>,
 
-    // nft price per year
-    pub price_per_year: Balance
+    // nft price per year by label length
+    pub price_tiers: PriceTiers,
+
+    //owner-managed yearly prices that override the length tier of specific names
+    pub premium_prices: UnorderedMap<TokenId, Balance>,
+
+    //decaying premium charged when a released name is registered again
+    pub release_auction: ReleaseAuction,
+
+    //pending commit-reveal registrations by committer and commitment hash
+    pub commitments: UnorderedMap<(AccountId, CryptoHash), Commitment>,
+
+    //registry of top-level domains names can be registered under
+    pub tlds: UnorderedMap<String, Tld>,
+
+    //reverse index from "{network}:{address}" to the primary name chosen for that address
+    pub primary_names: LookupMap<String, PrimaryName>,
+
+    //keeps track of token text records such as email, url or avatar
+    pub text_records_by_token_id: UnorderedMap<TokenId, HashMap<String, String>>,
+
+    //registry of chains names can record addresses for
+    pub chains: UnorderedMap<ChainKey, Chain>,
+
+    //keeps track of the binary contenthash a token points its website to
+    pub contenthash_by_token_id: LookupMap<TokenId, Vec<u8>>,
+
+    //custom resolver contracts names resolve through instead of the records stored here
+    pub resolvers_by_token_id: LookupMap<TokenId, AccountId>,
+
+    //accounts allowed to edit a token's records on behalf of its owner
+    pub managers_by_token_id: LookupMap<TokenId, AccountId>,
+
+    //keeps track of how many times a token's records were reset by a change of ownership
+    pub record_epochs: LookupMap<TokenId, RecordEpoch>,
+
+    //records previous owners offered along with their names, until the new owners accept them
+    pub carried_records: LookupMap<TokenId, CarriedRecords>,
+
+    //bytes of each name's records and manager paid for by each account, refunded when they are removed
+    pub record_storage_payers: LookupMap<TokenId, HashMap<AccountId, u64>>,
+
+    //NEP-145 storage balances accounts pay for records and approvals from
+    pub storage_balances: LookupMap<AccountId, Balance>,
 
+    //registration and renewal revenue the owner can withdraw
+    pub revenue: RevenueLedger,
+
+    //share of registration and renewal payments credited to referrers, in basis points
+    pub referral_rate: u32,
+    pub referrer_rewards: LookupMap<AccountId, ReferrerRewards>,
+
+    //allowlist window of a TLD launch, and how many registrations each leaf of each allowlist was used for
+    pub presale: Option<Presale>,
+    pub presale_claims: LookupMap<CryptoHash, u32>,
+
+    //fungible tokens registrations can be paid in, with their prices and what was received in each
+    pub ft_price_tiers: UnorderedMap<AccountId, PriceTiers>,
+    pub ft_revenue: LookupMap<AccountId, FtRevenue>,
+
+    //USD prices and the oracle converting them to NEAR, if names can be paid for at USD prices
+    pub usd_pricing: Option<UsdPricing>,
+
+}
+
+/// Layout of the deployed contract, before names were priced by tier. `migrate` reads the
+/// state with it, since the current `Contract` can't deserialize it.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OldContract {
+    pub owner_id: AccountId,
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+    pub tokens_by_id: LookupMap<TokenId, Token>,
+    pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub addresses_by_token_id: UnorderedMap<TokenId, HashMap<String, String>>,
+    pub price_per_year: Balance,
 }
 
 /// Helper structure for keys of the persistent collections.
@@ -83,7 +198,24 @@ pub enum StorageKey {
     TokensPerType,
     TokensPerTypeInner { token_type_hash: CryptoHash },
     TokenTypesLocked,
-    AddressesByTokenId
+    AddressesByTokenId,
+    PremiumPrices,
+    Commitments,
+    Tlds,
+    PrimaryNames,
+    TextRecordsByTokenId,
+    Chains,
+    ContenthashByTokenId,
+    ResolversByTokenId,
+    ManagersByTokenId,
+    RecordEpochs,
+    StorageBalances,
+    ReferrerRewards,
+    PresaleClaims,
+    FtPriceTiers,
+    FtRevenue,
+    CarriedRecords,
+    RecordStoragePayers,
 }
 
 #[near_bindgen]
@@ -120,7 +252,7 @@ impl Contract {
     #[init]
     pub fn new(owner_id: AccountId, metadata: NFTContractMetadata) -> Self {
         //create a variable of type Self with all the fields initialized. 
-        let this = Self {
+        let mut this = Self {
             //Storage keys are simply the prefixes used for the collections. This helps avoid data collision
             tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
             tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
@@ -134,9 +266,35 @@ impl Contract {
                 Some(&metadata),
             ),
             addresses_by_token_id: UnorderedMap::new(StorageKey::AddressesByTokenId.try_to_vec().unwrap()),
-            price_per_year: DEFAULT_PRICE_PER_YEAR
+            price_tiers: PriceTiers::default(),
+            premium_prices: UnorderedMap::new(StorageKey::PremiumPrices.try_to_vec().unwrap()),
+            release_auction: ReleaseAuction::default(),
+            commitments: UnorderedMap::new(StorageKey::Commitments.try_to_vec().unwrap()),
+            tlds: UnorderedMap::new(StorageKey::Tlds.try_to_vec().unwrap()),
+            primary_names: LookupMap::new(StorageKey::PrimaryNames.try_to_vec().unwrap()),
+            text_records_by_token_id: UnorderedMap::new(StorageKey::TextRecordsByTokenId.try_to_vec().unwrap()),
+            chains: UnorderedMap::new(StorageKey::Chains.try_to_vec().unwrap()),
+            contenthash_by_token_id: LookupMap::new(StorageKey::ContenthashByTokenId.try_to_vec().unwrap()),
+            resolvers_by_token_id: LookupMap::new(StorageKey::ResolversByTokenId.try_to_vec().unwrap()),
+            managers_by_token_id: LookupMap::new(StorageKey::ManagersByTokenId.try_to_vec().unwrap()),
+            record_epochs: LookupMap::new(StorageKey::RecordEpochs.try_to_vec().unwrap()),
+            carried_records: LookupMap::new(StorageKey::CarriedRecords.try_to_vec().unwrap()),
+            record_storage_payers: LookupMap::new(StorageKey::RecordStoragePayers.try_to_vec().unwrap()),
+            storage_balances: LookupMap::new(StorageKey::StorageBalances.try_to_vec().unwrap()),
+            revenue: RevenueLedger::default(),
+            referral_rate: 0,
+            referrer_rewards: LookupMap::new(StorageKey::ReferrerRewards.try_to_vec().unwrap()),
+            presale: None,
+            presale_claims: LookupMap::new(StorageKey::PresaleClaims.try_to_vec().unwrap()),
+            ft_price_tiers: UnorderedMap::new(StorageKey::FtPriceTiers.try_to_vec().unwrap()),
+            ft_revenue: LookupMap::new(StorageKey::FtRevenue.try_to_vec().unwrap()),
+            usd_pricing: None,
         };
 
+        //names have always been registered under the default TLD
+        this.tlds.insert(&DEFAULT_TLD.to_string(), &Tld { config: TldConfig::default(), registrations: 0 });
+        this.internal_register_builtin_chains();
+
         //return the Contract object
         this
     }
@@ -144,30 +302,45 @@ impl Contract {
     #[private]
     #[init(ignore_state)]
     pub fn migrate(owner_id: AccountId, metadata: NFTContractMetadata) -> Self {
-        let this: Self = env::state_read().expect("Cannot deserialize");
+        //the deployed contract still has the layout from before names were priced by tier
+        let old: OldContract = env::state_read().expect("Cannot deserialize");
 
         assert_eq!(
             env::predecessor_account_id(),
-            this.owner_id,
+            old.owner_id,
             "Only owner"
         );
 
-        Self {
-            //Storage keys are simply the prefixes used for the collections. This helps avoid data collision
-            tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
-            tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
-            token_metadata_by_id: UnorderedMap::new(
-                StorageKey::TokenMetadataById.try_to_vec().unwrap(),
-            ),
-            //set the owner_id field equal to the passed in owner_id. 
-            owner_id,
-            metadata: LazyOption::new(
-                StorageKey::NFTContractMetadata.try_to_vec().unwrap(),
-                Some(&metadata),
-            ),
-            addresses_by_token_id: UnorderedMap::new(StorageKey::AddressesByTokenId.try_to_vec().unwrap()),
-            price_per_year: this.price_per_year
-        }
+        //everything added since starts out empty, like on a new deployment
+        let mut this = Self::new(owner_id, metadata);
+
+        //keep the existing collections as they are, including the lengths stored in their handles
+        this.tokens_per_owner = old.tokens_per_owner;
+        this.tokens_by_id = old.tokens_by_id;
+        this.token_metadata_by_id = old.token_metadata_by_id;
+        this.addresses_by_token_id = old.addresses_by_token_id;
+
+        //every name of the deployed contract was registered under the default TLD
+        this.tlds.insert(&DEFAULT_TLD.to_string(), &Tld {
+            config: TldConfig::default(),
+            registrations: this.token_metadata_by_id.len(),
+        });
+
+        //the single yearly price becomes the floor of every tier, so no name gets cheaper
+        let floor = |price: U128| U128(price.0.max(old.price_per_year));
+        let tiers = PriceTiers::default();
+        this.price_tiers = PriceTiers {
+            one_two_chars: floor(tiers.one_two_chars),
+            three_chars: floor(tiers.three_chars),
+            four_chars: floor(tiers.four_chars),
+            five_plus_chars: floor(tiers.five_plus_chars),
+        };
+
+        //what the deployed contract collected beyond its storage is revenue the owner can still withdraw
+        let storage_cost = env::storage_byte_cost() * Balance::from(env::storage_usage());
+        this.revenue.accrued = env::account_balance().saturating_sub(storage_cost);
+
+        this
     }
 }
 
@@ -176,7 +349,7 @@ mod tests {
     use super::*;
     use near_sdk::test_utils::{accounts, VMContextBuilder};
     use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env};
+    use near_sdk::{testing_env, PromiseResult};
     use near_sdk::json_types::{ValidAccountId, U128, U64};
     const STORAGE_FOR_MINT: Balance = 11280000000000000000000;
 
@@ -198,6 +371,44 @@ mod tests {
         (context, contract)
     }
 
+    fn token_metadata(title: &str) -> TokenMetadata {
+        TokenMetadata {
+            title: Some(title.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    // commits `committer` to registering the name to `owner_id` with the secret "secret", and waits until it can be revealed
+    fn commit_name(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        token_id: &str,
+        committer: AccountId,
+        owner_id: AccountId,
+        deposit: Balance,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(committer)
+            .attached_deposit(deposit)
+            .build()
+        );
+        let commitment = contract.make_commitment(token_id.to_string(), owner_id, "secret".to_string());
+        contract.commit(commitment);
+
+        let revealable_at = context.context.block_timestamp + 60_000_000_000;
+        context.block_timestamp(revealable_at);
+    }
+
     #[test]
     fn test_new() {
         let mut context = get_context(accounts(1));
@@ -225,12 +436,12 @@ mod tests {
         royalty.insert(accounts(1), 1000);
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
+            .predecessor_account_id(accounts(0))
             .attached_deposit(ONE_NEAR_ES_YOCTO / 5)
             .build()
         );
 
-        let token_id = "manhnv".to_string();
+        let token_id = "manhnv.btc".to_string();
         contract.nft_mint(
             token_id.clone(), 
             TokenMetadata {
@@ -249,6 +460,8 @@ mod tests {
             },
             accounts(1),
             Some(royalty),
+            None,
+            None,
         );
 
         let token_from_nft_token = contract.nft_token(token_id);
@@ -259,233 +472,2196 @@ mod tests {
     }
 
     #[test]
-    fn test_add_address() {
+    fn test_mint_normalizes_name() {
         let (mut context, mut contract) = setup_contract();
 
-        let mut royalty: HashMap<AccountId, u32> = HashMap::new();
-        royalty.insert(accounts(1), 1000);
-
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .attached_deposit(ONE_NEAR_ES_YOCTO / 5)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
             .build()
         );
 
-        let token_id = "manhnv".to_string();
-        contract.nft_mint(
-            token_id.clone(), 
-            TokenMetadata {
-                title: Some("manhnv".to_string()), 
-                description: Some("bitcoin domain provided by decentrailnet".to_string()), 
-                extra: Some("0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string()), 
-                media: Some("https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg".to_string()),
-                media_hash: None,
-                copies: None,
-                issued_at: None,
-                expires_at: None,
-                starts_at: None,
-                updated_at: None,
-                reference: None,
-                reference_hash: None,
-            },
-            accounts(1),
-            Some(royalty),
-        );
+        contract.nft_mint("MANHNV.btc ".to_string(), token_metadata("manhnv.btc"), accounts(1), None, None, None);
+
+        assert!(contract.nft_token("manhnv.btc".to_string()).is_some());
+        assert!(contract.nft_token("MANHNV.btc ".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_mint_multiple_years() {
+        let (mut context, mut contract) = setup_contract();
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(true)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 3 + DEFAULT_PRICE_PER_YEAR / 2)
             .build()
         );
 
-        let token_from_nft_token = contract.nft_token(token_id.clone());
-
-        let expires_time = contract.nft_token_expires(token_id.clone());
-
-        let ethereum_address = "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string();
-        let remind_word = "wallet 1".to_string();
+        let (expires_at, change) = contract.nft_mint("manhnv.btc".to_string(), token_metadata("manhnv.btc"), accounts(1), None, Some(3), None);
+        assert_eq!(expires_at, 3 * ONE_YEAR_NANOSECOND);
+        assert_eq!(change, DEFAULT_PRICE_PER_YEAR / 2);
+        assert_eq!(contract.nft_token_expires("manhnv.btc".to_string()), expires_at);
 
-        let addresses = vec![
-            AddressInput {
-                network: Network::Ethereum,
-                address: ethereum_address.clone()
-            },
-            AddressInput {
-                network: Network::NEAR,
-                address: ethereum_address.clone()
-            }
-        ];
+        // without `years`, register for as many years as the deposit covers
+        let (expires_at, change) = contract.nft_mint("manhnv2.btc".to_string(), token_metadata("manhnv2.btc"), accounts(1), None, None, None);
+        assert_eq!(expires_at, 3 * ONE_YEAR_NANOSECOND);
+        assert_eq!(change, DEFAULT_PRICE_PER_YEAR / 2);
+    }
 
-        let addresses_remove = vec![
-            AddressRemoveInput {
-                network: Network::Ethereum,
-            },
-        ];
+    #[test]
+    #[should_panic(expected = "INVALID_DEPOSIT")]
+    fn test_mint_years_not_covered() {
+        let (mut context, mut contract) = setup_contract();
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(false)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 2)
             .build()
         );
 
-        contract.insert_addresses(token_id.clone(), addresses);
+        contract.nft_mint("manhnv.btc".to_string(), token_metadata("manhnv.btc"), accounts(1), None, Some(3), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_NAME")]
+    fn test_mint_rejects_invalid_name() {
+        let (mut context, mut contract) = setup_contract();
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(true)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
             .build()
         );
 
-        let address1 = contract.get_address(token_id.clone(), Network::Ethereum);
-        assert!(address1.is_some(), "NOT_FOUND");
-        let address2 = contract.get_address(token_id.clone(), Network::NEAR);
-        assert!(address2.is_some(), "NOT_FOUND");
-        let address3 = contract.get_address(token_id.clone(), Network::Polkadot);
-        assert!(address3.is_none(), "NOT_FOUND");
+        contract.nft_mint("a..btc".to_string(), token_metadata("a..btc"), accounts(1), None, None, None);
+    }
+
+    #[test]
+    fn test_expired_name_lifecycle() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(false)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
             .build()
         );
-
-        contract.remove_addresses(token_id.clone(), addresses_remove);
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        let expires_at = contract.nft_token_expires(token_id.clone());
+        assert_eq!(contract.nft_token_status(token_id.clone()), Some(NameStatus::Active));
 
         testing_env!(context
             .predecessor_account_id(accounts(1))
-            .is_view(true)
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 100)
             .build()
         );
-
-        let address2 = contract.get_address(token_id.clone(), Network::Ethereum);
-        assert!(address2.is_none(), "NOT_FOUND");
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }]);
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(false)
+            .block_timestamp(expires_at + 1)
             .build()
         );
-
-        contract.reset_token_addresses(token_id.clone());
+        assert_eq!(contract.nft_token_status(token_id.clone()), Some(NameStatus::GracePeriod));
 
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(true)
+            .block_timestamp(expires_at + GRACE_PERIOD_NANOSECOND + 1)
             .build()
         );
+        assert_eq!(contract.nft_token_status(token_id.clone()), Some(NameStatus::Released));
 
-        let address3 = contract.get_address(token_id.clone(), Network::NEAR);
-        assert!(address3.is_none(), "NOT_FOUND");
-
+        let premium = contract.get_release_premium(token_id.clone()).0;
         testing_env!(context
-            .predecessor_account_id(accounts(1))
-            .is_view(false)
-            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 10 + DEFAULT_PRICE_PER_YEAR/77)
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR + premium)
             .build()
         );
 
-        let (expires, change) = contract.extend_token(token_id.clone());
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(2), None, None, None);
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains(r#""event":"name_released""#)));
 
-        let new_expires_date = contract.nft_token_expires(token_id.clone());
-        assert_eq!(
-            new_expires_date,
-            expires,
-            "NOT_EQUAL {} - {}",
-            new_expires_date,
-            expires
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(2));
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+        assert!(contract.get_address(token_id.clone(), Network::NEAR.to_string()).is_none());
+        // the new registration starts over from the first records epoch
+        assert_eq!(contract.get_records_epoch(token_id.clone()), U64(0));
+        assert_eq!(contract.nft_token_status(token_id), Some(NameStatus::Active));
+    }
+
+    #[test]
+    fn test_release_premium_decays() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+        let auction = contract.get_release_auction();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
         );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        assert_eq!(contract.get_release_premium(token_id.clone()), U128(0));
 
-        assert_eq!(
-            DEFAULT_PRICE_PER_YEAR/77,
-            change,
-            "NOT_EQUAL 2 {} - {}",
-            new_expires_date,
-            expires
+        let released_at = contract.nft_token_expires(token_id.clone()) + GRACE_PERIOD_NANOSECOND;
+        let half_way = released_at + u64::from(auction.duration_days) * ONE_DAY_NANOSECOND / 2;
+        testing_env!(context
+            .block_timestamp(half_way)
+            .build()
         );
 
-        assert_eq!(
-            new_expires_date / ONE_YEAR_NANOSECOND,
-            11,
-            "NOT_EQUAL 3 {} - {}",
-            new_expires_date,
-            11
+        let premium = contract.get_release_premium(token_id.clone()).0;
+        assert!(premium >= auction.start_premium.0 / 2 && premium < auction.start_premium.0);
+
+        let curve = contract.get_release_premium_curve(token_id.clone());
+        assert_eq!(curve.len(), auction.duration_days as usize + 1);
+        assert_eq!(curve.first().unwrap().premium, auction.start_premium);
+        assert_eq!(curve.last().unwrap().premium, U128(0));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR + premium)
+            .build()
         );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(2), None, None, None);
+        assert_eq!(contract.nft_token(token_id).unwrap().owner_id, accounts(2));
     }
 
     #[test]
-    fn test_price() {
+    #[should_panic(expected = "ONLY_NFT_OWNER")]
+    fn test_grace_period_renewal_only_owner() {
         let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
 
         testing_env!(context
             .predecessor_account_id(accounts(0))
-            .is_view(true)
-            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 10 + DEFAULT_PRICE_PER_YEAR/77)
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        let expires_at = contract.nft_token_expires(token_id.clone());
+
+        testing_env!(context
+            .block_timestamp(expires_at + 1)
+            .predecessor_account_id(accounts(2))
             .build()
         );
+        contract.extend_token(token_id, None);
+    }
 
-        let price_per_year = contract.nft_price_per_year();
+    #[test]
+    fn test_commit_reveal_registration() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+        let secret = "correct horse battery staple".to_string();
 
-        assert_eq!(
-            price_per_year,
-            DEFAULT_PRICE_PER_YEAR,
-            "Price per year mismatch {} - {}",
-            price_per_year,
-            DEFAULT_PRICE_PER_YEAR,
+        let commitment = contract.make_commitment(token_id.clone(), accounts(1), secret.clone());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 2)
+            .build()
         );
+        contract.commit(commitment);
+        assert_eq!(contract.get_commitments(None, None).len(), 1);
 
         testing_env!(context
-            .predecessor_account_id(accounts(0))
-            .is_view(false)
-            // .attached_deposit(DEFAULT_PRICE_PER_YEAR * 10 + DEFAULT_PRICE_PER_YEAR/77)
+            .block_timestamp(ONE_DAY_NANOSECOND / 2)
+            .attached_deposit(0)
             .build()
         );
+        let (expires_at, change) = contract.reveal_and_register(token_id.clone(), secret, token_metadata(&token_id), None, Some(2), None);
 
-        let new_price = DEFAULT_PRICE_PER_YEAR * 2;
+        assert_eq!(change, 0);
+        assert_eq!(expires_at, ONE_DAY_NANOSECOND / 2 + 2 * ONE_YEAR_NANOSECOND);
+        assert_eq!(contract.nft_token(token_id).unwrap().owner_id, accounts(1));
+        assert!(contract.get_commitments(None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "COMMITMENT_REQUIRES_DEPOSIT")]
+    fn test_commit_storage_deposit() {
+        let (mut context, mut contract) = setup_contract();
+
+        // a commitment is stored until revealed or expired, so 1 yocto can't pay for it
+        let commitment = contract.make_commitment("manhnv.btc".to_string(), accounts(1), "secret".to_string());
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.commit(commitment);
+    }
 
-        let new_price_per_year = contract.set_token_price_per_year(new_price);
+    #[test]
+    #[should_panic(expected = "COMMITMENT_TOO_NEW")]
+    fn test_reveal_too_early() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
 
-        let price_per_year = contract.nft_price_per_year();
+        let commitment = contract.make_commitment(token_id.clone(), accounts(1), "secret".to_string());
 
-        assert_eq!(
-            price_per_year,
-            new_price,
-            "NEW Price per year mismatch {} - {}",
-            price_per_year,
-            new_price,
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
         );
+        contract.commit(commitment);
+        contract.reveal_and_register(token_id.clone(), "secret".to_string(), token_metadata(&token_id), None, None, None);
     }
 
     #[test]
-    fn test_withdraw() {
+    fn test_clear_expired_commitment() {
         let (mut context, mut contract) = setup_contract();
 
-        
+        let commitment = contract.make_commitment("manhnv.btc".to_string(), accounts(1), "secret".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.commit(commitment.clone());
+
+        testing_env!(context
+            .block_timestamp(ONE_DAY_NANOSECOND + 1)
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build()
+        );
+        let refunded = contract.clear_expired_commitment(accounts(1), commitment);
 
-        let balance_before = env::account_balance();
+        assert_eq!(refunded, U128(DEFAULT_PRICE_PER_YEAR));
+        assert!(contract.get_commitments(None, None).is_empty());
+    }
 
-        let withdraw_amount = 5* ONE_NEAR_ES_YOCTO;
+    #[test]
+    fn test_cancel_commitment() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
 
+        // a reveal that fails doesn't keep the deposit until the commitment expires
+        commit_name(&mut context, &mut contract, &token_id, accounts(1), accounts(1), DEFAULT_PRICE_PER_YEAR);
         testing_env!(context
-            .predecessor_account_id(accounts(0))
-            .is_view(false)
             .attached_deposit(1)
             .build()
         );
+        let commitment = contract.make_commitment(token_id, accounts(1), "secret".to_string());
+        let refunded = contract.cancel_commitment(commitment);
 
-        let price_per_year = contract.withdraw(accounts(0), U128::from(withdraw_amount));
+        assert_eq!(refunded, U128(DEFAULT_PRICE_PER_YEAR));
+        assert!(contract.get_commitments(None, None).is_empty());
+    }
 
-        let balance_after = env::account_balance();
+    #[test]
+    #[should_panic(expected = "COMMITMENT_REQUIRED")]
+    fn test_mint_requires_commitment() {
+        let (mut context, mut contract) = setup_contract();
 
-        assert_eq!(
-            balance_after - balance_before,
-            withdraw_amount,
-            "WITHDRAW_BALANCE_MISMATCH: {} != {}, before: {}, after: {}",
-            balance_after - balance_before,
-            withdraw_amount,
-            balance_before,
-            balance_after
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
         );
+        contract.nft_mint("manhnv.btc".to_string(), token_metadata("manhnv.btc"), accounts(1), None, None, None);
+    }
+
+    #[test]
+    fn test_subdomains() {
+        let (mut context, mut contract) = setup_contract();
+        let parent_id = "alice.btc".to_string();
 
-        // let price_per_year = contract.withdraw(accounts(0), 100 * ONE_NEAR_ES_YOCTO);
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 2)
+            .build()
+        );
+        contract.nft_mint(parent_id.clone(), token_metadata(&parent_id), accounts(1), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let parent_expires_at = contract.nft_token_expires(parent_id.clone());
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        let expires_at = contract.create_subdomain(parent_id.clone(), "Pay".to_string(), accounts(2), token_metadata("pay.alice.btc"), Some(U64(u64::MAX)));
+        assert_eq!(expires_at, parent_expires_at);
+
+        let token = contract.nft_token("pay.alice.btc".to_string()).unwrap();
+        assert_eq!(token.owner_id, accounts(2));
+
+        // the subdomain owner manages its own records
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .build()
+        );
+        contract.insert_addresses("pay.alice.btc".to_string(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(2).to_string(),
+        }]);
+        assert_eq!(contract.get_address("pay.alice.btc".to_string(), Network::NEAR.to_string()), Some(accounts(2).to_string()));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.reclaim_subdomain("pay.alice.btc".to_string());
+        assert_eq!(contract.nft_token("pay.alice.btc".to_string()).unwrap().owner_id, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ONLY_NFT_OWNER")]
+    fn test_subdomain_only_parent_owner() {
+        let (mut context, mut contract) = setup_contract();
+        let parent_id = "alice.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(parent_id.clone(), token_metadata(&parent_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.create_subdomain(parent_id, "pay".to_string(), accounts(2), token_metadata("pay.alice.btc"), None);
+    }
+
+    #[test]
+    fn test_tld_registry() {
+        let (mut context, mut contract) = setup_contract();
+
+        let eth_price = DEFAULT_PRICE_PER_YEAR * 3;
+        contract.add_tld("ETH".to_string(), TldConfig {
+            registration_open: true,
+            max_label_length: 10,
+            default_media: Some("https://dnet.io/eth.png".to_string()),
+            price_tiers: Some(PriceTiers {
+                five_plus_chars: U128(eth_price),
+                ..PriceTiers::default()
+            }),
+        });
+        assert_eq!(contract.get_tlds(None, None).len(), 2);
+        assert_eq!(contract.quote_price("manhnv.eth".to_string(), 1), U128(eth_price));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(eth_price)
+            .build()
+        );
+        contract.nft_mint("manhnv.eth".to_string(), token_metadata("manhnv.eth"), accounts(1), None, None, None);
+
+        let tld = contract.get_tld("eth".to_string()).unwrap();
+        assert_eq!(tld.registrations, U64(1));
+        assert_eq!(contract.get_tld("btc".to_string()).unwrap().registrations, U64(0));
+        assert_eq!(
+            contract.nft_token("manhnv.eth".to_string()).unwrap().metadata.media,
+            Some("https://dnet.io/eth.png".to_string())
+        );
+
+        //media chosen by the registrant wins over the TLD default
+        contract.nft_mint("alice.eth".to_string(), TokenMetadata {
+            media: Some("https://alice.io/avatar.png".to_string()),
+            ..token_metadata("alice.eth")
+        }, accounts(1), None, None, None);
+        assert_eq!(
+            contract.nft_token("alice.eth".to_string()).unwrap().metadata.media,
+            Some("https://alice.io/avatar.png".to_string())
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .build()
+        );
+        contract.disable_tld("eth".to_string());
+        assert!(!contract.get_tld("eth".to_string()).unwrap().config.registration_open);
+    }
+
+    #[test]
+    #[should_panic(expected = "TLD_NOT_FOUND")]
+    fn test_mint_unknown_tld() {
+        let (mut context, mut contract) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint("manhnv.xyz".to_string(), token_metadata("manhnv.xyz"), accounts(1), None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "TLD_CLOSED")]
+    fn test_mint_closed_tld() {
+        let (mut context, mut contract) = setup_contract();
+
+        contract.disable_tld("btc".to_string());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint("manhnv.btc".to_string(), token_metadata("manhnv.btc"), accounts(1), None, None, None);
+    }
+
+    #[test]
+    fn test_primary_name() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+        let ethereum_address = "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![
+            AddressInput { network: Network::NEAR.to_string(), address: accounts(2).to_string() },
+            AddressInput { network: Network::Ethereum.to_string(), address: ethereum_address.clone() },
+        ]);
+        contract.set_primary_name(token_id.clone(), Network::Ethereum.to_string());
+        assert_eq!(contract.get_primary_name(Network::Ethereum.to_string(), ethereum_address.clone()), Some(token_id.clone()));
+
+        // the NEAR account recorded on the name claims it for itself
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .build()
+        );
+        contract.set_primary_name(token_id.clone(), Network::NEAR.to_string());
+        assert_eq!(contract.get_primary_name_for_account(accounts(2)), Some(token_id.clone()));
+        assert_eq!(contract.get_primary_name_for_account(accounts(1)), None);
+
+        // transferring the name unsets every primary name pointing at it
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer(accounts(3), token_id.clone(), None, None);
+        assert_eq!(contract.get_primary_name_for_account(accounts(2)), None);
+        assert_eq!(contract.get_primary_name(Network::Ethereum.to_string(), ethereum_address.clone()), None);
+        // and deletes them, so they don't come back if the name returns to its previous owner
+        assert!(contract.primary_names.get(&format!("Ethereum:{}", ethereum_address)).is_none());
+        assert!(contract.primary_names.get(&format!("NEAR:{}", accounts(2))).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "PRIMARY_NAME_CLAIMED: manhnv.btc")]
+    fn test_primary_name_claimed_by_other_name() {
+        let (mut context, mut contract) = setup_contract();
+        let ethereum_address = "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string();
+
+        for (token_id, owner_id) in [("manhnv.btc", accounts(1)), ("squatter.btc", accounts(2))] {
+            testing_env!(context
+                .predecessor_account_id(accounts(0))
+                .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+                .build()
+            );
+            contract.nft_mint(token_id.to_string(), token_metadata(token_id), owner_id.clone(), None, None, None);
+
+            testing_env!(context
+                .predecessor_account_id(owner_id)
+                .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+                .build()
+            );
+            contract.insert_addresses(token_id.to_string(), vec![
+                AddressInput { network: Network::Ethereum.to_string(), address: ethereum_address.clone() },
+            ]);
+            // the second name can record the address, but not take over its reverse record
+            contract.set_primary_name(token_id.to_string(), Network::Ethereum.to_string());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ONLY_RECORDED_ADDRESS")]
+    fn test_primary_name_only_recorded_address() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![
+            AddressInput { network: Network::NEAR.to_string(), address: accounts(2).to_string() },
+        ]);
+        contract.set_primary_name(token_id, Network::NEAR.to_string());
+    }
+
+    #[test]
+    fn test_text_records() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.set_text_records(token_id.clone(), vec![
+            TextRecordInput { key: "email".to_string(), value: "manhnv@example.com".to_string() },
+            TextRecordInput { key: "com.twitter".to_string(), value: "manhnv".to_string() },
+        ]);
+
+        assert_eq!(contract.get_text(token_id.clone(), "com.twitter".to_string()), Some("manhnv".to_string()));
+        assert_eq!(contract.get_text_records(token_id.clone()).len(), 2);
+
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.remove_text_records(token_id.clone(), vec!["email".to_string()]);
+        assert_eq!(contract.get_text(token_id.clone(), "email".to_string()), None);
+        assert_eq!(contract.get_text_records(token_id).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_TEXT_VALUE")]
+    fn test_text_record_value_too_long() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+
+        contract.set_text_records(token_id, vec![
+            TextRecordInput { key: "description".to_string(), value: "a".repeat(MAX_TEXT_VALUE_LENGTH + 1) },
+        ]);
+    }
+
+    #[test]
+    fn test_chain_registry() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        assert_eq!(contract.get_chains(None, None).len(), 8);
+        assert_eq!(contract.get_chain("Ethereum".to_string()).unwrap().chain_id, "slip44:60");
+
+        contract.add_chain("Avalanche".to_string(), Chain {
+            chain_id: "eip155:43114".to_string(),
+            name: "Avalanche C-Chain".to_string(),
+            address_format: AddressFormat::Evm,
+            deprecated: false,
+        });
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: "Avalanche".to_string(),
+            address: "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string(),
+        }]);
+        assert!(contract.get_address(token_id.clone(), "Avalanche".to_string()).is_some());
+
+        // deprecated chains keep resolving
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .build()
+        );
+        contract.deprecate_chain("Avalanche".to_string());
+        assert!(contract.get_address(token_id, "Avalanche".to_string()).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "CHAIN_NOT_FOUND")]
+    fn test_insert_address_unknown_chain() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+
+        contract.insert_addresses(token_id, vec![AddressInput {
+            network: "Aptos".to_string(),
+            address: "0x1".to_string(),
+        }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_ADDRESS: Bitcoin")]
+    fn test_insert_address_wrong_format() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhnv.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+
+        // a Solana key pasted into the Bitcoin slot
+        contract.insert_addresses(token_id, vec![AddressInput {
+            network: Network::Bitcoin.to_string(),
+            address: "So11111111111111111111111111111111111111112".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_contenthash() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.set_contenthash(token_id.clone(), ContentHash::Arweave("ys32Pt8uC7TrVxHdOLByOspfPEq2LO63wREHQIM9SJQ".to_string()));
+        assert_eq!(
+            contract.get_contenthash("ManhNG.btc".to_string()),
+            Some(ContentHash::Arweave("ys32Pt8uC7TrVxHdOLByOspfPEq2LO63wREHQIM9SJQ".to_string()))
+        );
+
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.remove_contenthash(token_id.clone());
+        assert_eq!(contract.get_contenthash(token_id), None);
+    }
+
+    #[test]
+    fn test_resolve_builtin_records() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(2).to_string(),
+        }]);
+        contract.set_text_records(token_id.clone(), vec![TextRecordInput { key: "url".to_string(), value: "https://dnet.io".to_string() }]);
+        contract.set_contenthash(token_id.clone(), ContentHash::Swarm("d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea162".to_string()));
+
+        assert_eq!(contract.resolve(token_id.clone(), "addr:NEAR".to_string()), Some(accounts(2).to_string()));
+        assert_eq!(contract.resolve(token_id.clone(), "text:url".to_string()), Some("https://dnet.io".to_string()));
+        assert_eq!(
+            contract.resolve(token_id.clone(), "contenthash".to_string()),
+            Some("bzz://d1de9994b4d039f6548d191eb26786769f580809256b4685ef316805265ea162".to_string())
+        );
+        assert_eq!(contract.resolve(token_id.clone(), "addr:Ethereum".to_string()), None);
+
+        match contract.resolve_via_resolver(token_id, "text:url".to_string()) {
+            PromiseOrValue::Value(value) => assert_eq!(value, Some("https://dnet.io".to_string())),
+            PromiseOrValue::Promise(_) => panic!("names without a resolver resolve locally"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_via_custom_resolver() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 100)
+            .build()
+        );
+        contract.set_resolver(token_id.clone(), Some(accounts(3)));
+        assert_eq!(contract.get_resolver(token_id.clone()), Some(accounts(3)));
+        assert!(matches!(
+            contract.resolve_via_resolver(token_id.clone(), "addr:NEAR".to_string()),
+            PromiseOrValue::Promise(_)
+        ));
+
+        // the resolver's answer is passed through the callback
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&Some("alice.near")).unwrap())],
+        );
+        assert_eq!(contract.on_resolve_via_resolver(), Some("alice.near".to_string()));
+
+        testing_env!(
+            context.build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert_eq!(contract.on_resolve_via_resolver(), None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build()
+        );
+        contract.set_resolver(token_id.clone(), None);
+        assert_eq!(contract.get_resolver(token_id), None);
+    }
+
+    #[test]
+    fn test_manager_edits_records() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 100)
+            .build()
+        );
+        contract.set_manager(token_id.clone(), Some(accounts(2)));
+        assert_eq!(contract.get_manager(token_id.clone()), Some(accounts(2)));
+
+        // the manager edits records on behalf of the owner
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(2).to_string(),
+        }]);
+        contract.set_text_records(token_id.clone(), vec![TextRecordInput { key: "email".to_string(), value: "manhng@dnet.io".to_string() }]);
+        assert_eq!(contract.get_address(token_id.clone(), Network::NEAR.to_string()), Some(accounts(2).to_string()));
+
+        // the manager is cleared when the name changes hands
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer(accounts(3), token_id.clone(), None, None);
+        assert_eq!(contract.get_manager(token_id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ONLY_NFT_OWNER")]
+    fn test_manager_cannot_set_manager() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 100)
+            .build()
+        );
+        contract.set_manager(token_id.clone(), Some(accounts(2)));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .build()
+        );
+        contract.set_manager(token_id, Some(accounts(3)));
+    }
+
+    #[test]
+    fn test_reverted_transfer_resets_records() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer_call(accounts(2), token_id.clone(), None, None, "".to_string());
+
+        // the receiver owns the name while `nft_on_transfer` runs
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(2).to_string(),
+        }]);
+        contract.set_manager(token_id.clone(), Some(accounts(3)));
+
+        // and then asks for the name to be returned
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&true).unwrap())],
+        );
+        assert!(!contract.nft_resolve_transfer(None, accounts(1), accounts(2), token_id.clone(), HashMap::new(), None));
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(1));
+        assert!(contract.get_token_addresses(token_id.clone()).is_empty());
+        assert_eq!(contract.get_manager(token_id.clone()), None);
+        assert_eq!(contract.get_records_epoch(token_id), U64(2));
+    }
+
+    #[test]
+    fn test_transfer_resets_records() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+        let near_address = vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }];
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), near_address.clone());
+        contract.set_text_records(token_id.clone(), vec![TextRecordInput { key: "email".to_string(), value: "manhng@dnet.io".to_string() }]);
+
+        // the seller's records don't follow the name to the buyer
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert!(contract.get_token_addresses(token_id.clone()).is_empty());
+        assert!(contract.get_text_records(token_id.clone()).is_empty());
+        assert_eq!(contract.get_records_epoch(token_id.clone()), U64(1));
+
+        // unless they are carried over explicitly, for one transfer
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), near_address);
+        contract.set_records_carry_over(token_id.clone(), true);
+
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer(accounts(3), token_id.clone(), None, None);
+        assert!(contract.get_token_addresses(token_id.clone()).is_empty());
+        assert_eq!(contract.get_records_epoch(token_id.clone()), U64(2));
+        assert!(!contract.get_records_carry_over(token_id.clone()));
+
+        // they only resolve once the new owner accepts them
+        let carried = contract.get_carried_records(token_id.clone()).unwrap();
+        assert_eq!(carried.addresses.unwrap().get(&Network::NEAR.to_string()), Some(&accounts(1).to_string()));
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.accept_carried_records(token_id.clone());
+        assert_eq!(contract.get_address(token_id.clone(), Network::NEAR.to_string()), Some(accounts(1).to_string()));
+        assert!(contract.get_carried_records(token_id).is_none());
+    }
+
+    #[test]
+    fn test_batch_resolve() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+        let expiring_id = "expiring.btc".to_string();
+        let resolved_id = "resolved.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(expiring_id.clone(), token_metadata(&expiring_id), accounts(1), None, None, None);
+
+        // keep one name active past the other's expiry
+        testing_env!(context
+            .attached_deposit(2 * DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, Some(2), None);
+        contract.nft_mint(resolved_id.clone(), token_metadata(&resolved_id), accounts(1), None, Some(2), None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }]);
+        contract.insert_addresses(resolved_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }]);
+        contract.set_resolver(resolved_id.clone(), Some(accounts(3)));
+
+        testing_env!(context
+            .block_timestamp(contract.nft_token_expires(expiring_id.clone()) + 1)
+            .build()
+        );
+        let networks = vec![Network::NEAR.to_string(), Network::Ethereum.to_string()];
+        let resolved = contract.batch_resolve(vec![
+            ResolveQuery { token_id: "ManhNG.btc".to_string(), networks: networks.clone() },
+            ResolveQuery { token_id: expiring_id, networks: networks.clone() },
+            ResolveQuery { token_id: "unknown.btc".to_string(), networks: networks.clone() },
+            ResolveQuery { token_id: "no-tld".to_string(), networks: networks.clone() },
+            ResolveQuery { token_id: resolved_id, networks },
+        ]);
+
+        assert_eq!(resolved.len(), 5);
+        assert_eq!(resolved[0].error, None);
+        assert_eq!(resolved[0].owner_id, Some(accounts(1)));
+        assert_eq!(resolved[0].status, Some(NameStatus::Active));
+        assert_eq!(resolved[0].addresses.len(), 1);
+        assert_eq!(resolved[0].addresses.get(&Network::NEAR.to_string()), Some(&accounts(1).to_string()));
+        assert_eq!(resolved[1].error.as_deref(), Some("NAME_EXPIRED"));
+        assert_eq!(resolved[1].status, Some(NameStatus::GracePeriod));
+        assert!(resolved[1].addresses.is_empty());
+        assert_eq!(resolved[2].error.as_deref(), Some("TOKEN_NOT_FOUND"));
+        assert!(resolved[3].error.as_ref().unwrap().starts_with("INVALID_NAME"));
+        // the records stored here aren't returned for a name that resolves elsewhere
+        assert_eq!(resolved[4].error.as_deref(), Some("USES_CUSTOM_RESOLVER"));
+        assert_eq!(resolved[4].resolver_id, Some(accounts(3)));
+        assert!(resolved[4].addresses.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "TOO_MANY_QUERIES")]
+    fn test_batch_resolve_limit() {
+        let (_, contract) = setup_contract();
+        let query = ResolveQuery { token_id: "manhng.btc".to_string(), networks: vec![Network::NEAR.to_string()] };
+        contract.batch_resolve(vec![query; MAX_BATCH_RESOLVE + 1]);
+    }
+
+    #[test]
+    fn test_nft_burn() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }]);
+        let subdomain_id = "pay.manhng.btc".to_string();
+        contract.create_subdomain(token_id.clone(), "pay".to_string(), accounts(3), token_metadata(&subdomain_id), None);
+
+        testing_env!(context
+            .attached_deposit(1)
+            .block_timestamp(ONE_DAY_NANOSECOND)
+            .build()
+        );
+        contract.nft_burn(token_id.clone(), None);
+        assert!(near_sdk::test_utils::get_logs().last().unwrap().contains(r#""standard":"nep171","version":"nft-1.0.0","event":"nft_burn""#));
+
+        assert!(contract.nft_token(token_id.clone()).is_none());
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+        assert!(contract.get_token_addresses(token_id.clone()).is_empty());
+        assert_eq!(contract.get_tld("btc".to_string()).unwrap().registrations, U64(0));
+        assert!(contract.record_epochs.get(&token_id).is_none());
+        // subdomains go with their parent
+        assert_eq!(contract.nft_token_status(subdomain_id.clone()), Some(NameStatus::Released));
+
+        // the name can be registered again straight away, without the old subdomains
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .block_timestamp(ONE_DAY_NANOSECOND + 1)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(2), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(2));
+        assert_eq!(contract.nft_token_status(subdomain_id.clone()), Some(NameStatus::Released));
+        assert!(contract.get_address(subdomain_id.clone(), Network::NEAR.to_string()).is_none());
+
+        // and the new owner can hand the subdomain out again
+        testing_env!(context
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        contract.create_subdomain(token_id, "pay".to_string(), accounts(2), token_metadata(&subdomain_id), None);
+        assert_eq!(contract.nft_token(subdomain_id.clone()).unwrap().owner_id, accounts(2));
+        assert_eq!(contract.nft_token_status(subdomain_id), Some(NameStatus::Active));
+    }
+
+    #[test]
+    fn test_reclaim_expired() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        // the release auction has to end first, as the premium is based on the registration
+        let auction_ends_at = contract.nft_token_expires(token_id.clone())
+            + GRACE_PERIOD_NANOSECOND
+            + u64::from(contract.get_release_auction().duration_days) * ONE_DAY_NANOSECOND;
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(auction_ends_at)
+            .attached_deposit(1)
+            .build()
+        );
+        contract.reclaim_expired(token_id.clone());
+
+        assert!(contract.nft_token(token_id.clone()).is_none());
+        assert_eq!(contract.nft_token_status(token_id), None);
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "RELEASE_AUCTION_ACTIVE")]
+    fn test_reclaim_expired_during_release_auction() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .block_timestamp(contract.nft_token_expires(token_id.clone()) + GRACE_PERIOD_NANOSECOND + 1)
+            .attached_deposit(1)
+            .build()
+        );
+        contract.reclaim_expired(token_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "NAME_NOT_RELEASED")]
+    fn test_reclaim_expired_in_grace_period() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .block_timestamp(contract.nft_token_expires(token_id.clone()) + 1)
+            .attached_deposit(1)
+            .build()
+        );
+        contract.reclaim_expired(token_id);
+    }
+
+    #[test]
+    fn test_storage_management() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+        let min_balance = contract.storage_balance_bounds().min.0;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        assert!(contract.storage_balance_of(accounts(1)).is_none());
+
+        testing_env!(context
+            .attached_deposit(min_balance + ONE_NEAR_ES_YOCTO / 10)
+            .build()
+        );
+        let balance = contract.storage_deposit(None, None);
+        assert_eq!(balance.total.0, min_balance + ONE_NEAR_ES_YOCTO / 10);
+        assert_eq!(balance.available.0, ONE_NEAR_ES_YOCTO / 10);
+
+        // records are paid from the storage balance when nothing is attached
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.insert_addresses(token_id.clone(), vec![AddressInput {
+            network: Network::NEAR.to_string(),
+            address: accounts(1).to_string(),
+        }]);
+        let after_insert = contract.storage_balance_of(accounts(1)).unwrap().available.0;
+        assert!(after_insert < ONE_NEAR_ES_YOCTO / 10);
+
+        // and removing them gives the storage back
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.remove_addresses(token_id.clone(), vec![AddressRemoveInput { network: Network::NEAR.to_string() }]);
+        assert!(contract.storage_balance_of(accounts(1)).unwrap().available.0 > after_insert);
+
+        // so does a transfer, to whoever paid for the records it clears. Everything they paid for is back then
+        let before_insert = contract.storage_balance_of(accounts(1)).unwrap().available.0;
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.set_text_records(token_id.clone(), vec![TextRecordInput { key: "email".to_string(), value: "manhng@dnet.io".to_string() }]);
+        assert!(contract.storage_balance_of(accounts(1)).unwrap().available.0 < before_insert);
+        testing_env!(context
+            .attached_deposit(1)
+            .build()
+        );
+        contract.nft_transfer(accounts(2), token_id, None, None);
+        assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available.0, ONE_NEAR_ES_YOCTO / 10 + 1);
+
+        let balance = contract.storage_withdraw(Some(U128(after_insert)));
+        assert_eq!(balance.total.0, min_balance + balance.available.0);
+        assert!(contract.storage_unregister(None));
+        assert!(contract.storage_balance_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "FORCE_UNREGISTER_NOT_SUPPORTED")]
+    fn test_storage_force_unregister() {
+        let (mut context, mut contract) = setup_contract();
+        let min_balance = contract.storage_balance_bounds().min.0;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(min_balance)
+            .build()
+        );
+        contract.storage_deposit(None, None);
+
+        testing_env!(context.attached_deposit(1).build());
+        contract.storage_unregister(Some(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "deposit it with storage_deposit")]
+    fn test_records_need_storage() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+        context.predecessor_account_id(accounts(1));
+
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.set_text_records(token_id, vec![TextRecordInput { key: "email".to_string(), value: "manhng@dnet.io".to_string() }]);
+    }
+
+    #[test]
+    fn test_add_address() {
+        let (mut context, mut contract) = setup_contract();
+
+        let mut royalty: HashMap<AccountId, u32> = HashMap::new();
+        royalty.insert(accounts(1), 1000);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(ONE_NEAR_ES_YOCTO / 5)
+            .build()
+        );
+
+        let token_id = "manhnv.btc".to_string();
+        contract.nft_mint(
+            token_id.clone(), 
+            TokenMetadata {
+                title: Some("manhnv".to_string()), 
+                description: Some("bitcoin domain provided by decentrailnet".to_string()), 
+                extra: Some("0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string()), 
+                media: Some("https://vcdn-sohoa.vnecdn.net/2022/03/08/bored-ape-nft-accidental-0-728-5490-8163-1646708401.jpg".to_string()),
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                reference: None,
+                reference_hash: None,
+            },
+            accounts(1),
+            Some(royalty),
+            None,
+            None,
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(true)
+            .build()
+        );
+
+        let token_from_nft_token = contract.nft_token(token_id.clone());
+
+        let expires_time = contract.nft_token_expires(token_id.clone());
+
+        let ethereum_address = "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string();
+        let remind_word = "wallet 1".to_string();
+
+        let addresses = vec![
+            AddressInput {
+                network: Network::Ethereum.to_string(),
+                address: ethereum_address.clone()
+            },
+            AddressInput {
+                network: Network::NEAR.to_string(),
+                address: accounts(2).to_string()
+            }
+        ];
+
+        let addresses_remove = vec![
+            AddressRemoveInput {
+                network: Network::Ethereum.to_string(),
+            },
+        ];
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(false)
+            .build()
+        );
+
+        contract.insert_addresses(token_id.clone(), addresses);
+        assert!(near_sdk::test_utils::get_logs().last().unwrap().starts_with(
+            r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"addr_set""#
+        ));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(true)
+            .build()
+        );
+
+        let address1 = contract.get_address(token_id.clone(), Network::Ethereum.to_string());
+        assert!(address1.is_some(), "NOT_FOUND");
+        let address2 = contract.get_address(token_id.clone(), Network::NEAR.to_string());
+        assert!(address2.is_some(), "NOT_FOUND");
+        let address3 = contract.get_address(token_id.clone(), Network::Polkadot.to_string());
+        assert!(address3.is_none(), "NOT_FOUND");
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(false)
+            .attached_deposit(1)
+            .build()
+        );
+
+        contract.remove_addresses(token_id.clone(), addresses_remove);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(true)
+            .build()
+        );
+
+        let address2 = contract.get_address(token_id.clone(), Network::Ethereum.to_string());
+        assert!(address2.is_none(), "NOT_FOUND");
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(false)
+            .build()
+        );
+
+        contract.reset_token_addresses(token_id.clone());
+        assert_eq!(
+            near_sdk::test_utils::get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"dnet_name_service","version":"1.0.0","event":"records_reset","data":[{"token_id":"manhnv.btc","epoch":"0","record_type":"addr"}]}"#
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(true)
+            .build()
+        );
+
+        let address3 = contract.get_address(token_id.clone(), Network::NEAR.to_string());
+        assert!(address3.is_none(), "NOT_FOUND");
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .is_view(false)
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 10 + DEFAULT_PRICE_PER_YEAR/77)
+            .build()
+        );
+
+        let (expires, change) = contract.extend_token(token_id.clone(), None);
+
+        let new_expires_date = contract.nft_token_expires(token_id.clone());
+        assert_eq!(
+            new_expires_date,
+            expires,
+            "NOT_EQUAL {} - {}",
+            new_expires_date,
+            expires
+        );
+
+        assert_eq!(
+            DEFAULT_PRICE_PER_YEAR/77,
+            change,
+            "NOT_EQUAL 2 {} - {}",
+            new_expires_date,
+            expires
+        );
+
+        assert_eq!(
+            new_expires_date / ONE_YEAR_NANOSECOND,
+            11,
+            "NOT_EQUAL 3 {} - {}",
+            new_expires_date,
+            11
+        );
+    }
+
+    #[test]
+    fn test_price() {
+        let (mut context, mut contract) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .is_view(true)
+            .build()
+        );
+
+        let price_per_year = contract.nft_name_price_per_year("manhnv.btc".to_string());
+
+        assert_eq!(
+            price_per_year.0,
+            DEFAULT_PRICE_PER_YEAR,
+            "Price per year mismatch {} - {}",
+            price_per_year.0,
+            DEFAULT_PRICE_PER_YEAR,
+        );
+
+        let tiers = contract.get_price_tiers();
+        assert_eq!(contract.quote_price("ab.btc".to_string(), 1), tiers.one_two_chars);
+        assert_eq!(contract.quote_price("abc.btc".to_string(), 1), tiers.three_chars);
+        assert_eq!(contract.quote_price("abcd.btc".to_string(), 1), tiers.four_chars);
+        assert_eq!(contract.quote_price("abcde.btc".to_string(), 3).0, tiers.five_plus_chars.0 * 3);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .is_view(false)
+            .build()
+        );
+
+        let new_price = DEFAULT_PRICE_PER_YEAR * 2;
+
+        contract.set_price_tiers(PriceTiers {
+            five_plus_chars: U128(new_price),
+            ..tiers
+        });
+        contract.set_premium_name("Bitcoin.btc".to_string(), U128(new_price * 100));
+
+        let price_per_year = contract.nft_name_price_per_year("manhnv.btc".to_string());
+
+        assert_eq!(
+            price_per_year.0,
+            new_price,
+            "NEW Price per year mismatch {} - {}",
+            price_per_year.0,
+            new_price,
+        );
+        assert_eq!(contract.quote_price("bitcoin.btc".to_string(), 2).0, new_price * 200);
+
+        contract.remove_premium_name("bitcoin.btc".to_string());
+        assert_eq!(contract.quote_price("bitcoin.btc".to_string(), 2).0, new_price * 2);
+    }
+
+    #[test]
+    fn test_migrate_deployed_state() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let token_id = "manhng.btc".to_string();
+
+        // state as written by the deployed contract, with one name holding an Ethereum address
+        let mut old = OldContract {
+            owner_id: accounts(0),
+            tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
+            tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
+            token_metadata_by_id: UnorderedMap::new(StorageKey::TokenMetadataById.try_to_vec().unwrap()),
+            metadata: LazyOption::new(StorageKey::NFTContractMetadata.try_to_vec().unwrap(), None),
+            addresses_by_token_id: UnorderedMap::new(StorageKey::AddressesByTokenId.try_to_vec().unwrap()),
+            price_per_year: DEFAULT_PRICE_PER_YEAR * 2,
+        };
+        let mut owner_tokens = UnorderedSet::new(
+            StorageKey::TokenPerOwnerInner { account_id_hash: hash_account_id(&accounts(1)) }.try_to_vec().unwrap(),
+        );
+        owner_tokens.insert(&token_id);
+        old.tokens_per_owner.insert(&accounts(1), &owner_tokens);
+        old.tokens_by_id.insert(&token_id, &Token {
+            owner_id: accounts(1),
+            approved_account_ids: Default::default(),
+            next_approval_id: 0,
+            royalty: HashMap::new(),
+        });
+        old.token_metadata_by_id.insert(&token_id, &TokenMetadata {
+            expires_at: Some(ONE_YEAR_NANOSECOND),
+            ..token_metadata(&token_id)
+        });
+        let mut addresses = HashMap::new();
+        addresses.insert("Ethereum".to_string(), "0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string());
+        old.addresses_by_token_id.insert(&token_id, &addresses);
+        env::state_write(&old);
+
+        let contract = Contract::migrate(accounts(0), NFTContractMetadata {
+            spec: "nft-1.0.0".to_string(),
+            name: "Dnet NFT".to_string(),
+            symbol: "DNFT".to_string(),
+            icon: None,
+            base_uri: None,
+            reference: None,
+            reference_hash: None,
+        });
+
+        assert_eq!(contract.nft_total_supply().0, 1);
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(1));
+        assert_eq!(contract.nft_tokens_for_owner(accounts(1), None, None).len(), 1);
+        assert_eq!(
+            contract.get_address(token_id.clone(), "Ethereum".to_string()),
+            Some("0x33ed1b1B29e807fCf15EC731b0c0DE18d306be1a".to_string())
+        );
+        // the old price is the floor of every tier
+        let tiers = contract.get_price_tiers();
+        assert_eq!(tiers.five_plus_chars.0, DEFAULT_PRICE_PER_YEAR * 2);
+        assert_eq!(tiers.one_two_chars, PriceTiers::default().one_two_chars);
+        assert!(contract.get_chain("Ethereum".to_string()).is_some());
+        assert_eq!(contract.get_tld("btc".to_string()).unwrap().registrations, U64(1));
+        // NEAR collected before the upgrade stays withdrawable
+        let surplus = env::account_balance() - env::storage_byte_cost() * Balance::from(env::storage_usage());
+        let revenue = contract.get_revenue();
+        assert!(surplus > 0);
+        assert_eq!(revenue.total.0, surplus);
+        assert_eq!(revenue.available.0, surplus);
+    }
+
+    #[test]
+    fn test_deprecated_price_per_year() {
+        let (mut context, mut contract) = setup_contract();
+        assert_eq!(contract.nft_price_per_year(), DEFAULT_PRICE_PER_YEAR);
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_token_price_per_year(DEFAULT_PRICE_PER_YEAR * 3);
+        assert_eq!(contract.nft_price_per_year(), DEFAULT_PRICE_PER_YEAR * 3);
+        assert_eq!(contract.nft_name_price_per_year("manhnv.btc".to_string()).0, DEFAULT_PRICE_PER_YEAR * 3);
+        //shorter names keep their own tiers
+        assert_eq!(contract.get_price_tiers().four_chars, PriceTiers::default().four_chars);
+    }
+
+    #[test]
+    #[should_panic(expected = "ONLY_CONTRACT_OWNER")]
+    fn test_set_price_tiers_only_owner() {
+        let (mut context, mut contract) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .build()
+        );
+
+        contract.set_price_tiers(PriceTiers::default());
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR * 2)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, Some(1), None);
+
+        testing_env!(context
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR + 1)
+            .build()
+        );
+        contract.extend_token(token_id, None);
+
+        let revenue = contract.get_revenue();
+        assert_eq!(revenue.total.0, 2 * DEFAULT_PRICE_PER_YEAR);
+        assert_eq!(revenue.available.0, 2 * DEFAULT_PRICE_PER_YEAR);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.withdraw(accounts(0), U128(DEFAULT_PRICE_PER_YEAR));
+
+        let revenue = contract.get_revenue();
+        assert_eq!(revenue.withdrawn.0, DEFAULT_PRICE_PER_YEAR);
+        assert_eq!(revenue.available.0, DEFAULT_PRICE_PER_YEAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "WITHDRAW_EXCEEDS_AVAILABLE_REVENUE")]
+    fn test_withdraw_more_than_revenue() {
+        let (mut context, mut contract) = setup_contract();
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.withdraw(accounts(0), U128(5 * ONE_NEAR_ES_YOCTO));
+    }
+
+    #[test]
+    fn test_withdraw_keeps_storage_deposits() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+        let deposit = ONE_NEAR_ES_YOCTO;
+        let storage_usage = 10_000;
+        let storage_cost = env::storage_byte_cost() * Balance::from(storage_usage);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, None, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(deposit)
+            .build()
+        );
+        contract.storage_deposit(None, None);
+
+        // half of the revenue is still in the contract, next to the storage deposit and the attached yocto
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .storage_usage(storage_usage)
+            .account_balance(storage_cost + deposit + DEFAULT_PRICE_PER_YEAR / 2 - 1)
+            .build()
+        );
+        assert_eq!(contract.get_revenue().available.0, DEFAULT_PRICE_PER_YEAR / 2);
+        contract.withdraw(accounts(0), U128(DEFAULT_PRICE_PER_YEAR / 2));
+
+        // which leaves exactly enough to refund the depositor
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .account_balance(storage_cost + deposit - 1)
+            .build()
+        );
+        assert_eq!(contract.get_revenue().available.0, 0);
+        let balance = contract.storage_withdraw(None);
+        assert_eq!(balance.available.0, 0);
+        assert!(contract.storage_unregister(None));
+    }
+
+    #[test]
+    fn test_referral_rewards() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_referral_rate(1_000);
+        assert_eq!(contract.get_referral_rate(), 1_000);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(1), None, Some(1), Some(accounts(3)));
+        contract.extend_token(token_id, Some(accounts(3)));
+
+        let rewards = contract.get_referrer_rewards(accounts(3));
+        assert_eq!(rewards.earned.0, 2 * DEFAULT_PRICE_PER_YEAR / 10);
+        assert_eq!(rewards.claimable.0, 2 * DEFAULT_PRICE_PER_YEAR / 10);
+        //referral rewards aren't the owner's to withdraw
+        let revenue = contract.get_revenue();
+        assert_eq!(revenue.total.0, 2 * DEFAULT_PRICE_PER_YEAR - rewards.earned.0);
+        assert_eq!(revenue.available.0, revenue.total.0);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.claim_referral_rewards();
+        let rewards = contract.get_referrer_rewards(accounts(3));
+        assert_eq!(rewards.claimed, rewards.earned);
+        assert_eq!(rewards.claimable.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_REFERRER")]
+    fn test_self_referral() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        commit_name(&mut context, &mut contract, &token_id, accounts(1), accounts(1), DEFAULT_PRICE_PER_YEAR);
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.reveal_and_register(token_id, "secret".to_string(), token_metadata("manhng.btc"), None, None, Some(accounts(1)));
+    }
+
+    //allowlist of accounts(1) reserving "manhng.btc" and accounts(2) with a quota of 2 names
+    fn setup_presale(context: &mut VMContextBuilder, contract: &mut Contract) -> (Vec<u8>, Vec<u8>) {
+        let reserved = env::sha256(format!("{}:manhng.btc", accounts(1)).as_bytes());
+        let quota = env::sha256(format!("{}:2", accounts(2)).as_bytes());
+        let root = if reserved <= quota {
+            env::sha256(&[reserved.as_slice(), quota.as_slice()].concat())
+        } else {
+            env::sha256(&[quota.as_slice(), reserved.as_slice()].concat())
+        };
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_presale(Presale {
+            tld: "btc".to_string(),
+            merkle_root: Base64VecU8(root),
+            starts_at: U64(0),
+            ends_at: U64(ONE_DAY_NANOSECOND),
+            discount: 5_000,
+        });
+        (reserved, quota)
+    }
+
+    #[test]
+    fn test_presale() {
+        let (mut context, mut contract) = setup_contract();
+        let (reserved, quota) = setup_presale(&mut context, &mut contract);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR / 2)
+            .build()
+        );
+        let token_id = "manhng.btc".to_string();
+        let (_, change) = contract.nft_mint_presale(
+            token_id.clone(),
+            token_metadata(&token_id),
+            accounts(1),
+            None,
+            Some(1),
+            None,
+            PresaleAllowance::Name(token_id.clone()),
+            vec![Base64VecU8(quota.clone())],
+        );
+        assert_eq!(change, 0);
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(1));
+        assert_eq!(contract.get_presale_claims(accounts(1), PresaleAllowance::Name(token_id)), 1);
+
+        for token_id in ["manhnv.btc", "manhnv2.btc"] {
+            testing_env!(context
+                .predecessor_account_id(accounts(2))
+                .attached_deposit(DEFAULT_PRICE_PER_YEAR / 2)
+                .build()
+            );
+            contract.nft_mint_presale(
+                token_id.to_string(),
+                token_metadata(token_id),
+                accounts(2),
+                None,
+                Some(1),
+                None,
+                PresaleAllowance::Quota(2),
+                vec![Base64VecU8(reserved.clone())],
+            );
+        }
+        assert_eq!(contract.get_presale_claims(accounts(2), PresaleAllowance::Quota(2)), 2);
+
+        //a new allowlist starts with unused allowances, even for the same leaves
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_presale(Presale { merkle_root: Base64VecU8(quota), ..contract.get_presale().unwrap() });
+        assert_eq!(contract.get_presale_claims(accounts(2), PresaleAllowance::Quota(2)), 0);
+
+        //once the presale is over anyone can register at the full price, proof or not
+        let token_id = "manhnv3.btc".to_string();
+        context.block_timestamp(ONE_DAY_NANOSECOND + 1);
+        commit_name(&mut context, &mut contract, &token_id, accounts(3), accounts(3), DEFAULT_PRICE_PER_YEAR);
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.reveal_and_register(token_id.clone(), "secret".to_string(), token_metadata(&token_id), None, Some(1), None);
+        assert_eq!(contract.nft_token(token_id).unwrap().owner_id, accounts(3));
+    }
+
+    #[test]
+    fn test_presale_free_name() {
+        let (mut context, mut contract) = setup_contract();
+        let (_, quota) = setup_presale(&mut context, &mut contract);
+        contract.set_presale(Presale { discount: MAX_PRESALE_DISCOUNT, ..contract.get_presale().unwrap() });
+
+        // a reserved name can be given away, for as many years as the caller asks for
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build()
+        );
+        let token_id = "manhng.btc".to_string();
+        let (expires_at, change) = contract.nft_mint_presale(
+            token_id.clone(),
+            token_metadata(&token_id),
+            accounts(1),
+            None,
+            Some(2),
+            None,
+            PresaleAllowance::Name(token_id.clone()),
+            vec![Base64VecU8(quota)],
+        );
+        assert_eq!(change, 0);
+        assert_eq!(expires_at, 2 * ONE_YEAR_NANOSECOND);
+        assert_eq!(contract.get_revenue().total.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "YEARS_REQUIRED")]
+    fn test_presale_free_name_without_years() {
+        let (mut context, mut contract) = setup_contract();
+        let (_, quota) = setup_presale(&mut context, &mut contract);
+        contract.set_presale(Presale { discount: MAX_PRESALE_DISCOUNT, ..contract.get_presale().unwrap() });
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build()
+        );
+        let token_id = "manhng.btc".to_string();
+        contract.nft_mint_presale(
+            token_id.clone(),
+            token_metadata(&token_id),
+            accounts(1),
+            None,
+            None,
+            None,
+            PresaleAllowance::Name(token_id.clone()),
+            vec![Base64VecU8(quota)],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "PRESALE_ALLOWANCE_USED")]
+    fn test_presale_quota_used() {
+        let (mut context, mut contract) = setup_contract();
+        let (reserved, _) = setup_presale(&mut context, &mut contract);
+
+        for token_id in ["manhnv.btc", "manhnv2.btc", "manhnv3.btc"] {
+            testing_env!(context
+                .predecessor_account_id(accounts(2))
+                .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+                .build()
+            );
+            contract.nft_mint_presale(
+                token_id.to_string(),
+                token_metadata(token_id),
+                accounts(2),
+                None,
+                None,
+                None,
+                PresaleAllowance::Quota(2),
+                vec![Base64VecU8(reserved.clone())],
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "PRESALE_ONLY")]
+    fn test_mint_during_presale() {
+        let (mut context, mut contract) = setup_contract();
+        setup_presale(&mut context, &mut contract);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(DEFAULT_PRICE_PER_YEAR)
+            .build()
+        );
+        let token_id = "manhnv.btc".to_string();
+        contract.nft_mint(token_id.clone(), token_metadata(&token_id), accounts(3), None, None, None);
+    }
+
+    //accounts(5) plays a stablecoin with 6 decimals, selling 5+ character names for 10 tokens a year
+    fn setup_ft(context: &mut VMContextBuilder, contract: &mut Contract) -> Balance {
+        let price_per_year = 10_000_000;
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_ft_price_tiers(accounts(5), PriceTiers {
+            one_two_chars: U128(100 * price_per_year),
+            three_chars: U128(50 * price_per_year),
+            four_chars: U128(10 * price_per_year),
+            five_plus_chars: U128(price_per_year),
+        });
+        price_per_year
+    }
+
+    #[test]
+    fn test_ft_payment() {
+        let (mut context, mut contract) = setup_contract();
+        let price_per_year = setup_ft(&mut context, &mut contract);
+        let token_id = "manhng.btc".to_string();
+
+        // the sender commits to the name first, and gets the commitment's deposit back
+        commit_name(&mut context, &mut contract, &token_id, accounts(1), accounts(1), ONE_NEAR_ES_YOCTO / 10);
+        let registered_at = context.context.block_timestamp;
+        testing_env!(context
+            .predecessor_account_id(accounts(5))
+            .attached_deposit(0)
+            .build()
+        );
+        let msg = format!(r#"{{"action": "mint", "token_id": "{}", "metadata": {{"title": "{}"}}, "secret": "secret"}}"#, token_id, token_id);
+        let unused = contract.ft_on_transfer(accounts(1), U128(2 * price_per_year + 5), msg);
+        assert!(matches!(unused, PromiseOrValue::Value(U128(5))));
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(1));
+        assert!(contract.get_commitments(None, None).is_empty());
+        let expires_at = contract.nft_token_expires(token_id.clone());
+        assert_eq!(expires_at, registered_at + 2 * ONE_YEAR_NANOSECOND);
+
+        let msg = format!(r#"{{"action": "renew", "token_id": "{}"}}"#, token_id);
+        let unused = contract.ft_on_transfer(accounts(2), U128(price_per_year), msg);
+        assert!(matches!(unused, PromiseOrValue::Value(U128(0))));
+        assert_eq!(contract.nft_token_expires(token_id), expires_at + ONE_YEAR_NANOSECOND);
+
+        //FT revenue is kept apart from NEAR revenue
+        let revenue = contract.get_ft_revenue(accounts(5));
+        assert_eq!(revenue.total.0, 3 * price_per_year);
+        assert_eq!(contract.get_revenue().total.0, 0);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build()
+        );
+        contract.withdraw_ft(accounts(5), accounts(0), U128(price_per_year));
+        assert_eq!(contract.get_ft_revenue(accounts(5)).available.0, 2 * price_per_year);
+
+        // a failed transfer can be withdrawn again
+        testing_env!(
+            context.attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_ft_withdraw(accounts(5), U128(price_per_year));
+        assert_eq!(contract.get_ft_revenue(accounts(5)).available.0, 3 * price_per_year);
+    }
+
+    #[test]
+    #[should_panic(expected = "FT_NOT_ACCEPTED")]
+    fn test_ft_payment_not_accepted() {
+        let (mut context, mut contract) = setup_contract();
+        setup_ft(&mut context, &mut contract);
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let msg = r#"{"action": "mint", "token_id": "manhng.btc", "metadata": {"title": "manhng.btc"}}"#.to_string();
+        contract.ft_on_transfer(accounts(1), U128(10_000_000), msg);
+    }
+
+    #[test]
+    #[should_panic(expected = "TLD_PRICED_IN_NEAR_ONLY: eth")]
+    fn test_ft_payment_tld_prices() {
+        let (mut context, mut contract) = setup_contract();
+        setup_ft(&mut context, &mut contract);
+        contract.add_tld("eth".to_string(), TldConfig {
+            price_tiers: Some(PriceTiers::default()),
+            ..TldConfig::default()
+        });
+
+        // the token's prices don't know about the TLD's own tiers
+        testing_env!(context.predecessor_account_id(accounts(5)).build());
+        let msg = r#"{"action": "mint", "token_id": "manhng.eth", "metadata": {"title": "manhng.eth"}, "secret": "secret"}"#.to_string();
+        contract.ft_on_transfer(accounts(1), U128(10_000_000), msg);
+    }
+
+    //accounts(4) plays the oracle. Names of 5+ characters cost $5 a year, which is 1 NEAR at $5 per NEAR
+    fn setup_usd_pricing(context: &mut VMContextBuilder, contract: &mut Contract) {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_usd_pricing(UsdPricing {
+            oracle_id: accounts(4),
+            price_tiers: PriceTiers {
+                one_two_chars: U128(50_000),
+                three_chars: U128(10_000),
+                four_chars: U128(2_000),
+                five_plus_chars: U128(500),
+            },
+            max_quote_age: U64(60_000_000_000),
+        });
+    }
+
+    //payment with a 1% slippage tolerance
+    fn usd_payment(payer_id: AccountId, deposit: Balance, expected_price: u128) -> UsdPayment {
+        UsdPayment { payer_id, deposit: U128(deposit), expected_price: U128(expected_price), slippage: 100, referrer: None }
+    }
+
+    fn usd_mint(token_id: &str, years: Option<u32>) -> UsdMint {
+        UsdMint {
+            token_id: token_id.to_string(),
+            metadata: token_metadata(token_id),
+            receiver_id: accounts(1),
+            perpetual_royalties: None,
+            years,
+        }
+    }
+
+    //the oracle quote a callback receives, with NEAR at `price` cents
+    fn oracle_result(price: u128, timestamp: u64) -> Vec<PromiseResult> {
+        let quote = NearUsdQuote { price: U128(price), decimals: 2, timestamp: U64(timestamp) };
+        vec![PromiseResult::Successful(near_sdk::serde_json::to_vec(&quote).unwrap())]
+    }
+
+    #[test]
+    fn test_usd_pricing() {
+        let (mut context, mut contract) = setup_contract();
+        setup_usd_pricing(&mut context, &mut contract);
+        let token_id = "manhng.btc".to_string();
+        assert_eq!(contract.nft_name_price_per_year_usd(token_id.clone()).0, 500);
+
+        // the commitment's deposit is part of the payment
+        commit_name(&mut context, &mut contract, &token_id, accounts(1), accounts(1), ONE_NEAR_ES_YOCTO);
+        let registered_at = context.context.block_timestamp;
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(ONE_NEAR_ES_YOCTO)
+            .build()
+        );
+        contract.nft_mint_usd(token_id.clone(), token_metadata(&token_id), accounts(1), None, Some(1), U128(500), 100, "secret".to_string(), None);
+        assert!(contract.get_commitments(None, None).is_empty());
+
+        // the oracle quotes $5 per NEAR, so one year costs 1 NEAR and the rest is refunded
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            oracle_result(500, 0),
+        );
+        let expires_at = contract.on_usd_mint(usd_payment(accounts(1), 2 * ONE_NEAR_ES_YOCTO, 500), usd_mint(&token_id, Some(1)));
+        assert_eq!(expires_at, Some(U64(registered_at + ONE_YEAR_NANOSECOND)));
+        assert_eq!(contract.nft_token(token_id.clone()).unwrap().owner_id, accounts(1));
+        assert_eq!(contract.get_revenue().total.0, ONE_NEAR_ES_YOCTO);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(2 * ONE_NEAR_ES_YOCTO)
+            .build()
+        );
+        contract.extend_token_usd(token_id.clone(), U128(1_000), 100, None);
+
+        // at $10 per NEAR, 2 NEAR renews for 4 years
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            oracle_result(1_000, 0),
+        );
+        let expires_at = contract.on_usd_renew(usd_payment(accounts(2), 2 * ONE_NEAR_ES_YOCTO, 1_000), token_id.clone());
+        assert_eq!(expires_at, Some(U64(registered_at + 5 * ONE_YEAR_NANOSECOND)));
+        assert_eq!(contract.nft_token_expires(token_id), registered_at + 5 * ONE_YEAR_NANOSECOND);
+        assert_eq!(contract.get_revenue().total.0, 3 * ONE_NEAR_ES_YOCTO);
+    }
+
+    #[test]
+    fn test_usd_pricing_refunds() {
+        let (mut context, mut contract) = setup_contract();
+        setup_usd_pricing(&mut context, &mut contract);
+        let token_id = "manhng.btc".to_string();
+
+        // a failed oracle call, a stale or future quote, a price beyond the slippage tolerance, a quote the deposit
+        // doesn't cover and amounts too large to compute are all refunded instead of registering the name
+        let huge_price = 10u128.pow(32);
+        for (results, block_timestamp, expected_price, years) in [
+            (vec![PromiseResult::Failed], 0, 500, 1),
+            (oracle_result(500, 0), 60_000_000_001, 500, 1),
+            (oracle_result(500, u64::MAX), 0, 500, 1),
+            (oracle_result(494, 0), 0, 500, 1),
+            (oracle_result(490, 0), 0, 490, 1),
+            (oracle_result(500, 0), 0, u128::MAX, 1),
+            (oracle_result(huge_price, 0), 0, huge_price, u32::MAX),
+        ] {
+            testing_env!(
+                context.predecessor_account_id(accounts(0)).block_timestamp(block_timestamp).build(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                results,
+            );
+            let expires_at = contract.on_usd_mint(usd_payment(accounts(1), ONE_NEAR_ES_YOCTO, expected_price), usd_mint(&token_id, Some(years)));
+            assert_eq!(expires_at, None);
+            assert!(contract.nft_token(token_id.clone()).is_none());
+        }
+        assert_eq!(contract.get_revenue().total.0, 0);
+    }
+
+    #[test]
+    fn test_usd_pricing_referral() {
+        let (mut context, mut contract) = setup_contract();
+        setup_usd_pricing(&mut context, &mut contract);
+        contract.set_referral_rate(1_000);
+        let token_id = "manhng.btc".to_string();
+
+        // the referrer's share comes out of the price, like for payments in NEAR
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            oracle_result(500, 0),
+        );
+        let payment = UsdPayment { referrer: Some(accounts(3)), ..usd_payment(accounts(1), ONE_NEAR_ES_YOCTO, 500) };
+        assert!(contract.on_usd_mint(payment, usd_mint(&token_id, Some(1))).is_some());
+        assert_eq!(contract.get_referrer_rewards(accounts(3)).earned.0, ONE_NEAR_ES_YOCTO / 10);
+        assert_eq!(contract.get_revenue().total.0, ONE_NEAR_ES_YOCTO - ONE_NEAR_ES_YOCTO / 10);
+
+        // referring this contract is refunded rather than panicking with the deposit held
+        let token_id = "manhnv.btc".to_string();
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            oracle_result(500, 0),
+        );
+        let payment = UsdPayment { referrer: Some(env::current_account_id()), ..usd_payment(accounts(1), ONE_NEAR_ES_YOCTO, 500) };
+        assert!(contract.on_usd_mint(payment, usd_mint(&token_id, Some(1))).is_none());
+        assert!(contract.nft_token(token_id).is_none());
+        assert_eq!(contract.get_referrer_rewards(env::current_account_id()).earned.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_REFERRER")]
+    fn test_referral_to_contract() {
+        let (mut context, mut contract) = setup_contract();
+        let token_id = "manhng.btc".to_string();
+
+        commit_name(&mut context, &mut contract, &token_id, accounts(1), accounts(1), DEFAULT_PRICE_PER_YEAR);
+        testing_env!(context
+            .attached_deposit(0)
+            .build()
+        );
+        contract.reveal_and_register(token_id, "secret".to_string(), token_metadata("manhng.btc"), None, None, Some(env::current_account_id()));
+    }
+
+    #[test]
+    #[should_panic(expected = "INVALID_YEARS")]
+    fn test_usd_pricing_years_overflow() {
+        let (mut context, mut contract) = setup_contract();
+        setup_usd_pricing(&mut context, &mut contract);
+        let token_id = "manhng.btc".to_string();
+
+        // checked before asking the oracle, as the expiry wouldn't fit in a timestamp
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(ONE_NEAR_ES_YOCTO)
+            .build()
+        );
+        contract.nft_mint_usd(token_id.clone(), token_metadata(&token_id), accounts(1), None, Some(u32::MAX), U128(500), 100, "secret".to_string(), None);
     }
 }
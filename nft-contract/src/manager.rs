@@ -0,0 +1,31 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    //let another account edit the name's records, e.g. a hot wallet for a name kept in cold storage.
+    //managers can't transfer or approve the name and are removed when it changes hands. `None` revokes the manager
+    #[payable]
+    pub fn set_manager(&mut self, token_id: TokenId, manager_id: Option<AccountId>) {
+        let initial_storage_usage = env::storage_usage();
+        // assert if function caller is not the owner
+        self.assert_token_owner(&token_id);
+
+        self.assert_token_expires(&token_id);
+
+        match &manager_id {
+            Some(manager_id) => self.managers_by_token_id.insert(&token_id, manager_id),
+            None => self.managers_by_token_id.remove(&token_id),
+        };
+        log_name_service_event(EventLogVariant::ManagerChanged(vec![ManagerChangedLog {
+            token_id: token_id.clone(),
+            manager_id: manager_id.map(|manager_id| manager_id.to_string()),
+        }]));
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+
+    pub fn get_manager(&self, token_id: TokenId) -> Option<AccountId> {
+        let token_id = normalize_token_id(&token_id);
+        self.managers_by_token_id.get(&token_id)
+    }
+}
@@ -10,16 +10,92 @@ impl Contract {
         receiver_id: AccountId,
         //we add an optional parameter for perpetual royalties
         perpetual_royalties: Option<HashMap<AccountId, u32>>,
-        
-    ) {
-        //measure the initial storage being used on the contract
-        // let initial_storage_usage = env::storage_usage();
+        //number of years to register for. If not given, as many whole years as the deposit covers
+        years: Option<u32>,
+        //partner credited with a share of the payment, see `set_referral_rate`
+        referrer: Option<AccountId>,
+    ) -> (u64, u128) {
+        //a plaintext name can be front-run, so the public registers through `commit` and `reveal_and_register`.
+        //the owner registers reserved names and airdrops directly
+        assert!(
+            env::predecessor_account_id() == self.owner_id,
+            "COMMITMENT_REQUIRED: register with commit and reveal_and_register"
+        );
+        // validate the name and register it under its canonical form
+        let domain = assert_valid_domain(&token_id);
+        self.assert_not_in_presale(&domain);
 
+        self.internal_paid_mint(&domain, metadata, receiver_id, perpetual_royalties, years, referrer, env::attached_deposit(), 0)
+    }
+}
+
+impl Contract {
+    //registers the name paid for with `payment`, less a discount in basis points, and refunds
+    //the caller whatever the payment covered beyond the registered years
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn internal_paid_mint(
+        &mut self,
+        domain: &DomainName,
+        metadata: TokenMetadata,
+        receiver_id: AccountId,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        years: Option<u32>,
+        referrer: Option<AccountId>,
+        payment: Balance,
+        discount: u32,
+    ) -> (u64, u128) {
+        assert_valid_referrer(referrer.as_ref());
+        let (years, price) = self.internal_registration_cost(domain, years, payment, discount);
+        self.internal_accrue_revenue(price, referrer.as_ref());
+
+        let expires_at = self.internal_mint(domain, metadata, receiver_id, perpetual_royalties, years);
+
+        let change = payment - price;
+        if change > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(change);
+        }
+
+        (expires_at, change)
+    }
+
+    //number of years a payment registers the name for, and the part of the payment that is spent
+    pub(crate) fn internal_registration_cost(&self, domain: &DomainName, years: Option<u32>, payment: Balance, discount: u32) -> (u64, Balance) {
+        //subdomains aren't sold, they are created by the owner of their parent name
+        assert!(!domain.is_subdomain(), "INVALID_NAME: subdomains cannot be minted directly");
+        self.assert_tld_open(domain);
+        let price_per_year = self.internal_price_per_year(domain);
+        let price_per_year = price_per_year - price_per_year * Balance::from(discount) / Balance::from(MAX_PRESALE_DISCOUNT);
+        // a released name also costs the current auction premium, which keeps decaying
+        // until the transaction lands, so any excess payment is refunded by the caller
+        let premium = self.internal_release_premium(&domain.to_string());
+        assert!(payment >= price_per_year + premium, "INVALID_DEPOSIT");
+        let years = match years {
+            Some(years) => {
+                assert!(years > 0, "MINT_AT_LEAST_ONE_YEAR");
+                u64::from(years)
+            }
+            //a name the presale gives away for free can't be paid for in years
+            None => {
+                assert!(price_per_year > 0, "YEARS_REQUIRED: the name is free");
+                u64::try_from((payment - premium) / price_per_year).ok().unwrap()
+            }
+        };
+        let price = price_per_year * Balance::from(years) + premium;
+        assert!(payment >= price, "INVALID_DEPOSIT");
+        (years, price)
+    }
+
+    //registers the name to the receiver for the given number of years, returning the new expiry
+    pub(crate) fn internal_mint(
+        &mut self,
+        domain: &DomainName,
+        metadata: TokenMetadata,
+        receiver_id: AccountId,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        years: u64,
+    ) -> u64 {
+        let token_id = domain.to_string();
 
-        let deposit_balance = env::attached_deposit();
-        // check if extend_ttl is None
-        assert_eq!(deposit_balance, self.price_per_year, "INVALID_DEPOSIT");
-        
         // create a royalty map to store in the token
         let mut royalty = HashMap::new();
 
@@ -46,15 +122,26 @@ impl Contract {
             royalty,
         };
 
+        //a name past its grace period is released, so clear the previous registration before minting it again
+        if let Some(status) = self.internal_token_status(&token_id) {
+            assert!(status == NameStatus::Released, "Token already exists");
+            self.internal_release_token(&token_id);
+        }
+
         //insert the token ID and token struct and make sure that the token doesn't exist
         assert!(
             self.tokens_by_id.insert(&token_id, &token).is_none(),
             "Token already exists"
         );
 
+        //names without media of their own are shown with the default media of their TLD
+        let media = metadata.media.clone().or_else(|| {
+            self.tlds.get(&domain.tld).and_then(|tld| tld.config.default_media)
+        });
+
         let current_block_timestamp = env::block_timestamp();
         let metadata_with_time = TokenMetadata {
-            media: Some(String::from("https://bafybeie4sb64l2tevfwrt6dtr7z32seliyybw4g5nanxt4dbdokn2fyk6m.ipfs.dweb.link/dnet.png")),
+            media,
             issued_at: Some(current_block_timestamp),
             expires_at: Some(current_block_timestamp),
             starts_at: Some(current_block_timestamp),
@@ -67,9 +154,10 @@ impl Contract {
 
         //call the internal method for adding the token to the owner
         self.internal_add_token_to_owner(&token.owner_id, &token_id);
+        self.internal_update_tld_registrations(&token_id, true);
 
         
-        self.internal_extend_token(&token_id, ONE_YEAR_NANOSECOND);
+        let expires_at = self.internal_extend_token(&token_id, years * ONE_YEAR_NANOSECOND);
 
         // Construct the mint log as per the events standard.
         let nft_mint_log: EventLog = EventLog {
@@ -91,10 +179,6 @@ impl Contract {
         // Log the serialized json.
         env::log_str(&nft_mint_log.to_string());
 
-        //calculate the required storage which was the used - initial
-        // let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-
-        //refund any excess storage if the user attached too much. Panic if they didn't attach enough to cover the required.
-        // refund_deposit(required_storage_in_bytes);
+        expires_at
     }
-}
\ No newline at end of file
+}
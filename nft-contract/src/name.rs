@@ -0,0 +1,142 @@
+use crate::*;
+
+/// Maximum length of a single label, e.g. "manhng" in "manhng.btc".
+pub const MAX_LABEL_LENGTH: usize = 63;
+/// Minimum length of a top-level domain, e.g. "btc".
+pub const MIN_TLD_LENGTH: usize = 2;
+/// Maximum length of a top-level domain.
+pub const MAX_TLD_LENGTH: usize = 16;
+/// Maximum length of a full domain name including the dots.
+pub const MAX_DOMAIN_LENGTH: usize = 253;
+
+/// A domain name split into its labels and top-level domain.
+/// "pay.alice.btc" is stored as labels ["pay", "alice"] and tld "btc".
+#[derive(Debug, Clone, PartialEq)]
+pub struct DomainName {
+    pub labels: Vec<String>,
+    pub tld: String,
+}
+
+impl DomainName {
+    /// Normalizes and validates a token ID, returning the reason it was rejected on failure.
+    pub fn parse(token_id: &str) -> Result<Self, String> {
+        let normalized = normalize_token_id(token_id);
+
+        if normalized.is_empty() {
+            return Err("name is empty".to_string());
+        }
+        if normalized.len() > MAX_DOMAIN_LENGTH {
+            return Err(format!("name is longer than {} characters", MAX_DOMAIN_LENGTH));
+        }
+
+        let mut parts: Vec<&str> = normalized.split('.').collect();
+        if parts.len() < 2 {
+            return Err("name has no top-level domain".to_string());
+        }
+
+        let tld = parts.pop().unwrap();
+        validate_tld(tld)?;
+        for label in parts.iter() {
+            validate_label(label)?;
+        }
+
+        Ok(Self {
+            labels: parts.iter().map(|label| label.to_string()).collect(),
+            tld: tld.to_string(),
+        })
+    }
+
+    /// The label directly under the top-level domain ("alice" for "pay.alice.btc").
+    pub fn name_label(&self) -> &str {
+        self.labels.last().unwrap()
+    }
+
+    pub fn is_subdomain(&self) -> bool {
+        self.labels.len() > 1
+    }
+
+    /// The name one level up ("alice.btc" for "pay.alice.btc"), if this is a subdomain.
+    pub fn parent(&self) -> Option<DomainName> {
+        if !self.is_subdomain() {
+            return None;
+        }
+        Some(Self {
+            labels: self.labels[1..].to_vec(),
+            tld: self.tld.clone(),
+        })
+    }
+}
+
+impl std::fmt::Display for DomainName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}", self.labels.join("."), self.tld)
+    }
+}
+
+/// Canonical form used for lookups: surrounding whitespace trimmed and lowercased.
+pub(crate) fn normalize_token_id(token_id: &str) -> TokenId {
+    token_id.trim().to_lowercase()
+}
+
+/// Parses a token ID into its canonical domain name, panicking with the reason if it is malformed.
+pub(crate) fn assert_valid_domain(token_id: &str) -> DomainName {
+    DomainName::parse(token_id)
+        .unwrap_or_else(|reason| panic!("INVALID_NAME: {}", reason))
+}
+
+fn validate_label(label: &str) -> Result<(), String> {
+    if label.is_empty() {
+        return Err("name contains an empty label".to_string());
+    }
+    if label.len() > MAX_LABEL_LENGTH {
+        return Err(format!("label \"{}\" is longer than {} characters", label, MAX_LABEL_LENGTH));
+    }
+    if let Some(c) = label.chars().find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-')) {
+        return Err(format!("label \"{}\" contains invalid character {:?}", label, c));
+    }
+    if label.starts_with('-') || label.ends_with('-') {
+        return Err(format!("label \"{}\" cannot start or end with a hyphen", label));
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_tld(tld: &str) -> Result<(), String> {
+    if tld.len() < MIN_TLD_LENGTH || tld.len() > MAX_TLD_LENGTH {
+        return Err(format!(
+            "top-level domain \"{}\" must be between {} and {} characters",
+            tld, MIN_TLD_LENGTH, MAX_TLD_LENGTH
+        ));
+    }
+    if !tld.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("top-level domain \"{}\" must only contain letters", tld));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_normalizes_case_and_whitespace() {
+        let domain = DomainName::parse(" MANHNG.btc ").unwrap();
+        assert_eq!(domain.labels, vec!["manhng".to_string()]);
+        assert_eq!(domain.tld, "btc");
+        assert_eq!(domain.to_string(), "manhng.btc");
+        assert!(!domain.is_subdomain());
+
+        let domain = DomainName::parse("pay.alice.btc").unwrap();
+        assert_eq!(domain.name_label(), "alice");
+        assert!(domain.is_subdomain());
+        assert_eq!(domain.parent().unwrap().to_string(), "alice.btc");
+        assert!(domain.parent().unwrap().parent().is_none());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_names() {
+        for name in ["", "manhng", "manhng .btc", "a..btc", ".btc", "manhng.", "-a.btc", "a-.btc", "a_b.btc", "a.b1", "a.x"] {
+            assert!(DomainName::parse(name).is_err(), "{:?} should be rejected", name);
+        }
+        assert!(DomainName::parse(&format!("{}.btc", "a".repeat(MAX_LABEL_LENGTH + 1))).is_err());
+    }
+}
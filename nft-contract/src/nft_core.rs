@@ -72,7 +72,7 @@ trait NonFungibleTokenResolver {
     this is stored on THIS contract and is meant to analyze what happened in the cross contract call when nft_on_transfer was called
     as part of the nft_transfer_call method
 */ 
-trait NonFungibleTokenResolver {
+pub trait NonFungibleTokenResolver {
     fn nft_resolve_transfer(
         &mut self,
         //we introduce an authorized ID for logging the transfer event
@@ -279,6 +279,10 @@ impl NonFungibleTokenResolver for Contract {
 
         //we inset the token back into the tokens_by_id collection
         self.tokens_by_id.insert(&token_id, &token);
+        //the receiver owned the name during the call, so whatever it recorded doesn't go back with the name
+        self.managers_by_token_id.remove(&token_id);
+        self.internal_remove_primary_names(&token_id);
+        self.internal_records_on_transfer(&token_id);
 
         /*
             We need to log that the NFT was reverted back to the original owner.
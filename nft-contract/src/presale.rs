@@ -0,0 +1,179 @@
+use crate::*;
+
+//presale discounts are in basis points of the yearly price, like royalties
+pub const MAX_PRESALE_DISCOUNT: u32 = 10_000;
+
+/// Allowlist registration window for a TLD launch. Until `ends_at` names under `tld` can
+/// only be registered with `nft_mint_presale`, by accounts in the Merkle tree whose root is
+/// `merkle_root`. Leaves are `sha256("{account_id}:{name}")` for a reserved name or
+/// `sha256("{account_id}:{quota}")` for a number of names of the account's choice, and
+/// pairs are hashed in sorted order, so a proof is just the list of sibling hashes.
+/// Claims are counted per Merkle root, so every allowlist starts with unused allowances.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Presale {
+    pub tld: String,
+    pub merkle_root: Base64VecU8,
+    pub starts_at: U64,
+    pub ends_at: U64,
+    //discount on the yearly price during the presale
+    pub discount: u32,
+}
+
+/// What an allowlisted account may register during the presale.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum PresaleAllowance {
+    //one specific reserved name
+    Name(TokenId),
+    //this many names of the account's choice
+    Quota(u32),
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_presale(&mut self, presale: Presale) {
+        self.assert_only_owner();
+        assert!(self.tlds.get(&presale.tld).is_some(), "TLD_NOT_FOUND: {}", presale.tld);
+        assert!(presale.merkle_root.0.len() == 32, "INVALID_MERKLE_ROOT");
+        assert!(presale.starts_at.0 < presale.ends_at.0, "INVALID_PRESALE_WINDOW");
+        assert!(presale.discount <= MAX_PRESALE_DISCOUNT, "INVALID_PRESALE_DISCOUNT");
+        self.presale = Some(presale);
+    }
+
+    pub fn remove_presale(&mut self) {
+        self.assert_only_owner();
+        self.presale = None;
+    }
+
+    pub fn get_presale(&self) -> Option<Presale> {
+        self.presale.clone()
+    }
+
+    //number of registrations the allowance of the leaf `sha256("{account_id}:{name or quota}")` has been used
+    //for under the current allowlist
+    pub fn get_presale_claims(&self, account_id: AccountId, allowance: PresaleAllowance) -> u32 {
+        self.presale.as_ref().map_or(0, |presale| {
+            let key = presale_claim_key(&presale.merkle_root, presale_leaf(&account_id, &allowance));
+            self.presale_claims.get(&key).unwrap_or(0)
+        })
+    }
+
+    //registers a name with the caller's allowlist allowance during the presale. Once the presale
+    //is over, or when the name's TLD has none, this is the same as `nft_mint` and the proof is ignored
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_mint_presale(
+        &mut self,
+        token_id: TokenId,
+        metadata: TokenMetadata,
+        receiver_id: AccountId,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        years: Option<u32>,
+        referrer: Option<AccountId>,
+        allowance: PresaleAllowance,
+        //sibling hashes from the caller's leaf up to the Merkle root
+        proof: Vec<Base64VecU8>,
+    ) -> (u64, u128) {
+        let domain = assert_valid_domain(&token_id);
+        let presale = match self.internal_presale_for(&domain) {
+            Some(presale) => presale,
+            None => return self.nft_mint(token_id, metadata, receiver_id, perpetual_royalties, years, referrer),
+        };
+        assert!(env::block_timestamp() >= presale.starts_at.0, "PRESALE_NOT_STARTED");
+
+        let leaf = presale_leaf(&env::predecessor_account_id(), &allowance);
+        assert!(verify_merkle_proof(leaf, &proof, &presale.merkle_root), "INVALID_PROOF");
+
+        let limit = match allowance {
+            PresaleAllowance::Name(name) => {
+                assert!(normalize_token_id(&name) == domain.to_string(), "PRESALE_NAME_MISMATCH");
+                1
+            }
+            PresaleAllowance::Quota(quota) => quota,
+        };
+        let key = presale_claim_key(&presale.merkle_root, leaf);
+        let claims = self.presale_claims.get(&key).unwrap_or(0);
+        assert!(claims < limit, "PRESALE_ALLOWANCE_USED");
+        self.presale_claims.insert(&key, &(claims + 1));
+
+        self.internal_paid_mint(&domain, metadata, receiver_id, perpetual_royalties, years, referrer, env::attached_deposit(), presale.discount)
+    }
+}
+
+impl Contract {
+    //the presale the name falls under, if it hasn't ended yet
+    pub(crate) fn internal_presale_for(&self, domain: &DomainName) -> Option<Presale> {
+        self.presale
+            .clone()
+            .filter(|presale| presale.tld == domain.tld && env::block_timestamp() <= presale.ends_at.0)
+    }
+
+    //names under a TLD in presale can only be registered through `nft_mint_presale` until it ends
+    pub(crate) fn assert_not_in_presale(&self, domain: &DomainName) {
+        assert!(self.internal_presale_for(domain).is_none(), "PRESALE_ONLY: {}", domain.tld);
+    }
+}
+
+pub(crate) fn presale_leaf(account_id: &AccountId, allowance: &PresaleAllowance) -> CryptoHash {
+    let leaf = match allowance {
+        PresaleAllowance::Name(name) => format!("{}:{}", account_id, normalize_token_id(name)),
+        PresaleAllowance::Quota(quota) => format!("{}:{}", account_id, quota),
+    };
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(leaf.as_bytes()));
+    hash
+}
+
+//claims are stored under `sha256(merkle_root || leaf)`, so the same leaf in a later allowlist isn't already used up
+fn presale_claim_key(merkle_root: &Base64VecU8, leaf: CryptoHash) -> CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(&[merkle_root.0.as_slice(), &leaf].concat()));
+    hash
+}
+
+//hashes the leaf up the tree, each pair in sorted order, and compares the result with the root
+fn verify_merkle_proof(leaf: CryptoHash, proof: &[Base64VecU8], root: &Base64VecU8) -> bool {
+    let mut hash = leaf.to_vec();
+    for sibling in proof {
+        let (first, second) = if hash <= sibling.0 { (&hash, &sibling.0) } else { (&sibling.0, &hash) };
+        hash = env::sha256(&[first.as_slice(), second.as_slice()].concat());
+    }
+    hash == root.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+        if a <= b {
+            env::sha256(&[a, b].concat())
+        } else {
+            env::sha256(&[b, a].concat())
+        }
+    }
+
+    #[test]
+    fn merkle_proofs() {
+        let leaves: Vec<Vec<u8>> = ["alice.near:manhng.btc", "bob.near:3", "carol.near:1"]
+            .iter()
+            .map(|leaf| env::sha256(leaf.as_bytes()))
+            .collect();
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let root = Base64VecU8(hash_pair(&left, &leaves[2]));
+
+        let alice = presale_leaf(&"alice.near".parse().unwrap(), &PresaleAllowance::Name("ManhNG.btc".to_string()));
+        let proof = vec![Base64VecU8(leaves[1].clone()), Base64VecU8(leaves[2].clone())];
+        assert!(verify_merkle_proof(alice, &proof, &root));
+
+        let carol = presale_leaf(&"carol.near".parse().unwrap(), &PresaleAllowance::Quota(1));
+        assert!(verify_merkle_proof(carol, &[Base64VecU8(left)], &root));
+
+        //a bigger quota than the one allowlisted isn't in the tree
+        let bob = presale_leaf(&"bob.near".parse().unwrap(), &PresaleAllowance::Quota(4));
+        let proof = vec![Base64VecU8(leaves[0].clone()), Base64VecU8(leaves[2].clone())];
+        assert!(!verify_merkle_proof(bob, &proof, &root));
+    }
+}
@@ -1,14 +1,136 @@
 use crate::*;
 
+/// Yearly registration price by length of the name label ("manhng" in "manhng.btc").
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceTiers {
+    pub one_two_chars: U128,
+    pub three_chars: U128,
+    pub four_chars: U128,
+    pub five_plus_chars: U128,
+}
+
+impl Default for PriceTiers {
+    fn default() -> Self {
+        Self {
+            one_two_chars: U128(ONE_NEAR_ES_YOCTO * 10),
+            three_chars: U128(ONE_NEAR_ES_YOCTO * 5),
+            four_chars: U128(ONE_NEAR_ES_YOCTO),
+            five_plus_chars: U128(DEFAULT_PRICE_PER_YEAR),
+        }
+    }
+}
+
+impl PriceTiers {
+    pub(crate) fn price_for_label(&self, label: &str) -> Balance {
+        match label.len() {
+            0..=2 => self.one_two_chars.0,
+            3 => self.three_chars.0,
+            4 => self.four_chars.0,
+            _ => self.five_plus_chars.0,
+        }
+    }
+
+    pub(crate) fn assert_valid(&self) {
+        for price in [self.one_two_chars, self.three_chars, self.four_chars, self.five_plus_chars] {
+            assert_min_price(price.0);
+        }
+    }
+}
+
+fn assert_min_price(price_per_year: Balance) {
+    assert!(price_per_year >= DEFAULT_PRICE_PER_YEAR, "price is at least: {}", DEFAULT_PRICE_PER_YEAR);
+}
+
 #[near_bindgen]
 impl Contract {
+    pub fn nft_name_price_per_year(&self, token_id: TokenId) -> U128 {
+        let domain = assert_valid_domain(&token_id);
+        U128(self.internal_price_per_year(&domain))
+    }
+
+    //deprecated, use `nft_name_price_per_year`. Price of names with 5 or more characters under the contract-wide tiers
     pub fn nft_price_per_year(&self) -> Balance {
-        self.price_per_year
+        self.price_tiers.five_plus_chars.0
     }
 
+    //deprecated, use `set_price_tiers`. Sets the price of names with 5 or more characters under the contract-wide tiers
     pub fn set_token_price_per_year(&mut self, price_per_year: Balance) {
+        let mut price_tiers = self.price_tiers.clone();
+        price_tiers.five_plus_chars = U128(price_per_year);
+        self.set_price_tiers(price_tiers);
+    }
+
+    pub fn quote_price(&self, name: TokenId, years: u32) -> U128 {
+        assert!(years > 0, "YEARS_MUST_BE_POSITIVE");
+        let domain = assert_valid_domain(&name);
+        U128(self.internal_price_per_year(&domain) * Balance::from(years))
+    }
+
+    pub fn get_price_tiers(&self) -> PriceTiers {
+        self.price_tiers.clone()
+    }
+
+    pub fn get_premium_names(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<(TokenId, U128)> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.premium_prices.iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(token_id, price)| (token_id, U128(price)))
+            .collect()
+    }
+
+    pub fn set_price_tiers(&mut self, price_tiers: PriceTiers) {
+        self.assert_only_owner();
+        price_tiers.assert_valid();
+        self.price_tiers = price_tiers;
+        log_price_changed("tiers", None, None);
+    }
+
+    pub fn set_premium_name(&mut self, token_id: TokenId, price_per_year: U128) {
         self.assert_only_owner();
-        assert!(price_per_year > DEFAULT_PRICE_PER_YEAR, "price is at least: {}", DEFAULT_PRICE_PER_YEAR);
-        self.price_per_year = price_per_year
+        assert_min_price(price_per_year.0);
+        let domain = assert_valid_domain(&token_id);
+        self.premium_prices.insert(&domain.to_string(), &price_per_year.0);
+        log_price_changed("premium", Some(domain.to_string()), Some(price_per_year.0));
+    }
+
+    pub fn remove_premium_name(&mut self, token_id: TokenId) {
+        self.assert_only_owner();
+        let token_id = normalize_token_id(&token_id);
+        if self.premium_prices.remove(&token_id).is_some() {
+            log_price_changed("premium", Some(token_id), None);
+        }
+    }
+}
+
+pub(crate) fn log_price_changed(kind: &str, target: Option<String>, price_per_year: Option<Balance>) {
+    log_name_service_event(EventLogVariant::PriceChanged(vec![PriceChangedLog {
+        kind: kind.to_string(),
+        target,
+        price_per_year: price_per_year.map(|price| price.to_string()),
+    }]));
+}
+
+impl Contract {
+    //premium names override the length tier of their label, and a TLD's own tiers override the contract-wide ones
+    pub(crate) fn internal_price_per_year(&self, domain: &DomainName) -> Balance {
+        if let Some(price) = self.premium_prices.get(&domain.to_string()) {
+            return price;
+        }
+        self.tlds
+            .get(&domain.tld)
+            .and_then(|tld| tld.config.price_tiers)
+            .unwrap_or_else(|| self.price_tiers.clone())
+            .price_for_label(domain.name_label())
+    }
+
+    //whether the name's TLD has its own tiers. Prices in fungible tokens and USD are contract-wide,
+    //so names under such a TLD are priced in NEAR only
+    pub(crate) fn internal_has_tld_prices(&self, domain: &DomainName) -> bool {
+        self.tlds
+            .get(&domain.tld)
+            .is_some_and(|tld| tld.config.price_tiers.is_some())
     }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+use crate::*;
+
+/// Version of a name's record set. Addresses, text records, the contenthash and the
+/// resolver are reset whenever the name changes hands, so a buyer never resolves to
+/// the seller's wallets, and `epoch` counts those resets. The owner can offer their
+/// records to the next owner instead, who decides whether to take them over. An entry
+/// is a fixed few bytes per name,
+/// so the ones written by resets are paid for by the contract rather than settled
+/// against anyone's storage balance.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct RecordEpoch {
+    pub epoch: u64,
+    //offer the records to the next owner, on the next transfer only. Re-registering a released name always resets them
+    pub carry_over: bool,
+}
+
+/// Records the previous owner offered along with the name. They don't resolve until the
+/// new owner accepts them with `accept_carried_records`, and are dropped on the next reset.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CarriedRecords {
+    pub addresses: Option<HashMap<String, String>>,
+    pub text_records: Option<HashMap<String, String>>,
+    pub contenthash: Option<Vec<u8>>,
+    pub resolver: Option<AccountId>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_records_carry_over(&mut self, token_id: TokenId, carry_over: bool) {
+        let initial_storage_usage = env::storage_usage();
+        // assert if function caller is not the owner
+        self.assert_token_owner(&token_id);
+
+        self.assert_token_expires(&token_id);
+
+        let mut record_epoch = self.record_epochs.get(&token_id).unwrap_or_default();
+        record_epoch.carry_over = carry_over;
+        self.record_epochs.insert(&token_id, &record_epoch);
+
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    pub fn get_records_epoch(&self, token_id: TokenId) -> U64 {
+        let token_id = normalize_token_id(&token_id);
+        U64(self.record_epochs.get(&token_id).unwrap_or_default().epoch)
+    }
+
+    pub fn get_records_carry_over(&self, token_id: TokenId) -> bool {
+        let token_id = normalize_token_id(&token_id);
+        self.record_epochs.get(&token_id).unwrap_or_default().carry_over
+    }
+
+    //records the previous owner offered with the name, for the new owner to review before accepting them
+    pub fn get_carried_records(&self, token_id: TokenId) -> Option<CarriedRecords> {
+        let token_id = normalize_token_id(&token_id);
+        self.carried_records.get(&token_id)
+    }
+
+    //take over the records the previous owner offered. They replace whatever was set since the transfer
+    #[payable]
+    pub fn accept_carried_records(&mut self, token_id: TokenId) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_token_owner(&token_id);
+        self.assert_token_expires(&token_id);
+
+        let carried = self.carried_records.remove(&token_id).expect("NO_CARRIED_RECORDS");
+        self.internal_take_records(&token_id);
+        //indexers see the accepted records as set again, on top of the reset of the transfer
+        log_name_service_event(EventLogVariant::RecordsReset(vec![RecordsResetLog {
+            token_id: token_id.clone(),
+            epoch: self.record_epochs.get(&token_id).unwrap_or_default().epoch.to_string(),
+            record_type: None,
+        }]));
+        if let Some(addresses) = carried.addresses {
+            self.addresses_by_token_id.insert(&token_id, &addresses);
+            log_name_service_event(EventLogVariant::AddrSet(
+                addresses
+                    .into_iter()
+                    .map(|(network, address)| AddrSetLog { token_id: token_id.clone(), network, address })
+                    .collect(),
+            ));
+        }
+        if let Some(text_records) = carried.text_records {
+            self.text_records_by_token_id.insert(&token_id, &text_records);
+            log_name_service_event(EventLogVariant::TextSet(
+                text_records
+                    .into_iter()
+                    .map(|(key, value)| TextSetLog { token_id: token_id.clone(), key, value })
+                    .collect(),
+            ));
+        }
+        if let Some(contenthash) = carried.contenthash {
+            self.contenthash_by_token_id.insert(&token_id, &contenthash);
+            log_name_service_event(EventLogVariant::ContenthashChanged(vec![ContenthashChangedLog {
+                token_id: token_id.clone(),
+                contenthash: ContentHash::decode(&contenthash).map(|contenthash| contenthash.to_uri()),
+            }]));
+        }
+        if let Some(resolver) = carried.resolver {
+            self.resolvers_by_token_id.insert(&token_id, &resolver);
+            log_name_service_event(EventLogVariant::ResolverChanged(vec![ResolverChangedLog {
+                token_id: token_id.clone(),
+                resolver_id: Some(resolver.to_string()),
+            }]));
+        }
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+}
+
+impl Contract {
+    //called when a name changes hands. Starts a new epoch with empty records. If the previous owner chose
+    //to carry them over, they are set aside for the new owner to accept
+    pub(crate) fn internal_records_on_transfer(&mut self, token_id: &TokenId) {
+        let carry_over = self.record_epochs.get(token_id).unwrap_or_default().carry_over;
+        let carried = if carry_over { Some(self.internal_take_records(token_id)) } else { None };
+        //carried records are still the previous owner's storage
+        let payers = if carry_over { self.record_storage_payers.remove(token_id) } else { None };
+
+        self.internal_reset_records(token_id);
+        if let Some(carried) = carried {
+            self.carried_records.insert(token_id, &carried);
+        }
+        if let Some(payers) = payers {
+            self.record_storage_payers.insert(token_id, &payers);
+        }
+    }
+
+    //removes every record of a name, refunding their storage, and starts a new epoch
+    pub(crate) fn internal_reset_records(&mut self, token_id: &TokenId) {
+        self.internal_take_records(token_id);
+        self.carried_records.remove(token_id);
+        self.internal_refund_record_storage(token_id);
+
+        let epoch = self.record_epochs.get(token_id).unwrap_or_default().epoch + 1;
+        self.record_epochs.insert(token_id, &RecordEpoch { epoch, carry_over: false });
+
+        log_name_service_event(EventLogVariant::RecordsReset(vec![RecordsResetLog {
+            token_id: token_id.to_string(),
+            epoch: epoch.to_string(),
+            record_type: None,
+        }]));
+    }
+
+    //removes the records a name resolves to and returns them
+    fn internal_take_records(&mut self, token_id: &TokenId) -> CarriedRecords {
+        CarriedRecords {
+            addresses: self.addresses_by_token_id.remove(token_id),
+            text_records: self.text_records_by_token_id.remove(token_id),
+            contenthash: self.contenthash_by_token_id.remove(token_id),
+            resolver: self.resolvers_by_token_id.remove(token_id),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::*;
+
+//referral rates are in basis points of the payment, like royalties
+pub const MAX_REFERRAL_RATE: u32 = 10_000;
+
+/// Rewards a referrer earned by bringing in registrations and renewals. Rewards are
+/// credited with each payment and stay in the contract until the referrer claims them.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct ReferrerRewards {
+    pub earned: Balance,
+    pub claimed: Balance,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonReferrerRewards {
+    pub earned: U128,
+    pub claimed: U128,
+    //what `claim_referral_rewards` transfers
+    pub claimable: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_referral_rate(&mut self, referral_rate: u32) {
+        self.assert_only_owner();
+        assert!(referral_rate <= MAX_REFERRAL_RATE, "INVALID_REFERRAL_RATE");
+        self.referral_rate = referral_rate;
+    }
+
+    pub fn get_referral_rate(&self) -> u32 {
+        self.referral_rate
+    }
+
+    pub fn get_referrer_rewards(&self, account_id: AccountId) -> JsonReferrerRewards {
+        let rewards = self.referrer_rewards.get(&account_id).unwrap_or_default();
+        JsonReferrerRewards {
+            earned: U128(rewards.earned),
+            claimed: U128(rewards.claimed),
+            claimable: U128(rewards.earned - rewards.claimed),
+        }
+    }
+
+    //transfers every reward the caller earned and hasn't claimed yet
+    #[payable]
+    pub fn claim_referral_rewards(&mut self) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut rewards = self.referrer_rewards.get(&account_id).unwrap_or_default();
+        let claimable = rewards.earned - rewards.claimed;
+        assert!(claimable > 0, "NO_REFERRAL_REWARDS");
+
+        rewards.claimed = rewards.earned;
+        self.referrer_rewards.insert(&account_id, &rewards);
+        self.revenue.referral_owed -= claimable;
+
+        Promise::new(account_id).transfer(claimable)
+    }
+}
+
+impl Contract {
+    //credits the referrer's share of a payment and returns it. The referrer is checked by the caller, see `check_referrer`
+    pub(crate) fn internal_credit_referrer(&mut self, referrer: &AccountId, payment: Balance) -> Balance {
+        let reward = payment * Balance::from(self.referral_rate) / Balance::from(MAX_REFERRAL_RATE);
+        if reward > 0 {
+            let mut rewards = self.referrer_rewards.get(referrer).unwrap_or_default();
+            rewards.earned += reward;
+            self.referrer_rewards.insert(referrer, &rewards);
+            self.revenue.referral_owed += reward;
+        }
+        reward
+    }
+}
+
+//the payer can't refer themselves, and rewards can't be credited to this contract
+pub(crate) fn check_referrer(referrer: Option<&AccountId>, payer_id: &AccountId) -> Result<(), &'static str> {
+    match referrer {
+        Some(referrer) if referrer == payer_id || *referrer == env::current_account_id() => Err("INVALID_REFERRER"),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn assert_valid_referrer(referrer: Option<&AccountId>) {
+    if let Err(err) = check_referrer(referrer, &env::predecessor_account_id()) {
+        panic!("{}: cannot refer yourself or this contract", err);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_RESOLVER_CALLBACK: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_RESOLVER: Gas = Gas(10_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+
+/// Keys a record can be resolved by:
+/// - "addr:<chain>" the address recorded for a chain, e.g. "addr:Ethereum"
+/// - "text:<key>" a text record, e.g. "text:com.twitter"
+/// - "contenthash" the gateway URI of the name's website, e.g. "ipfs://bafy..."
+pub const ADDRESS_KEY_PREFIX: &str = "addr:";
+pub const TEXT_KEY_PREFIX: &str = "text:";
+pub const CONTENTHASH_KEY: &str = "contenthash";
+
+//most queries one `batch_resolve` call answers, so a call stays within the gas of a view
+pub const MAX_BATCH_RESOLVE: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResolveQuery {
+    pub token_id: TokenId,
+    pub networks: Vec<ChainKey>,
+}
+
+/// Result of one `batch_resolve` query. `error` is set instead of failing the whole batch
+/// when the name is invalid, not registered, expired or resolves through a custom resolver,
+/// in which case no addresses are returned.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ResolvedName {
+    pub token_id: TokenId,
+    pub owner_id: Option<AccountId>,
+    pub status: Option<NameStatus>,
+    pub expires_at: Option<U64>,
+    //custom resolver the name's records have to be resolved through, see `resolve_via_resolver`
+    pub resolver_id: Option<AccountId>,
+    //addresses recorded for the requested networks. Networks without an address are left out
+    pub addresses: HashMap<ChainKey, String>,
+    pub error: Option<String>,
+}
+
+//interface of a custom resolver contract. This contract implements it too, serving the built-in records
+pub trait NameResolver {
+    fn resolve(&self, name: TokenId, key: String) -> Option<String>;
+}
+
+#[ext_contract(ext_resolver)]
+trait NameResolver {
+    fn resolve(&self, name: TokenId, key: String) -> Option<String>;
+}
+
+#[ext_contract(ext_resolver_callback)]
+trait ResolverCallback {
+    fn on_resolve_via_resolver(&self) -> Option<String>;
+}
+
+pub trait ResolverCallback {
+    fn on_resolve_via_resolver(&self) -> Option<String>;
+}
+
+#[near_bindgen]
+impl Contract {
+    //point a name to a custom resolver contract, or back to the records stored here with `None`
+    #[payable]
+    pub fn set_resolver(&mut self, token_id: TokenId, resolver_id: Option<AccountId>) {
+        let initial_storage_usage = env::storage_usage();
+        // assert if function caller is not the owner or manager
+        self.assert_token_owner_or_manager(&token_id);
+
+        self.assert_token_expires(&token_id);
+
+        match &resolver_id {
+            Some(resolver_id) => self.resolvers_by_token_id.insert(&token_id, resolver_id),
+            None => self.resolvers_by_token_id.remove(&token_id),
+        };
+        log_name_service_event(EventLogVariant::ResolverChanged(vec![ResolverChangedLog {
+            token_id: token_id.clone(),
+            resolver_id: resolver_id.map(|resolver_id| resolver_id.to_string()),
+        }]));
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+
+    pub fn get_resolver(&self, token_id: TokenId) -> Option<AccountId> {
+        let token_id = normalize_token_id(&token_id);
+        self.resolvers_by_token_id.get(&token_id)
+    }
+
+    //resolves a record through the name's resolver contract if it has one, or the built-in records otherwise.
+    //this has to be called as a transaction or from another contract since view calls can't make cross-contract calls
+    pub fn resolve_via_resolver(&self, name: TokenId, key: String) -> PromiseOrValue<Option<String>> {
+        let token_id = normalize_token_id(&name);
+        if self.is_token_expires(&token_id) {
+            return PromiseOrValue::Value(None);
+        }
+
+        match self.resolvers_by_token_id.get(&token_id) {
+            Some(resolver_id) => ext_resolver::resolve(
+                token_id,
+                key,
+                resolver_id, //contract account to make the call to
+                NO_DEPOSIT, //attached deposit
+                GAS_FOR_RESOLVER, //attached GAS
+            )
+            .then(ext_resolver_callback::on_resolve_via_resolver(
+                env::current_account_id(), //contract account to make the call to
+                NO_DEPOSIT, //attached deposit
+                GAS_FOR_RESOLVER_CALLBACK, //GAS attached to the call
+            ))
+            .into(),
+            None => PromiseOrValue::Value(self.resolve(token_id, key)),
+        }
+    }
+
+    //resolves many names from the records stored here in one call, e.g. a wallet's contact list
+    pub fn batch_resolve(&self, queries: Vec<ResolveQuery>) -> Vec<ResolvedName> {
+        assert!(queries.len() <= MAX_BATCH_RESOLVE, "TOO_MANY_QUERIES: at most {}", MAX_BATCH_RESOLVE);
+        queries.into_iter().map(|query| self.internal_resolve_query(query)).collect()
+    }
+}
+
+impl Contract {
+    fn internal_resolve_query(&self, query: ResolveQuery) -> ResolvedName {
+        let token_id = normalize_token_id(&query.token_id);
+        let mut resolved = ResolvedName {
+            token_id: token_id.clone(),
+            owner_id: None,
+            status: None,
+            expires_at: None,
+            resolver_id: None,
+            addresses: HashMap::new(),
+            error: None,
+        };
+
+        if let Err(reason) = DomainName::parse(&token_id) {
+            resolved.error = Some(format!("INVALID_NAME: {}", reason));
+            return resolved;
+        }
+        let token = match self.tokens_by_id.get(&token_id) {
+            Some(token) => token,
+            None => {
+                resolved.error = Some("TOKEN_NOT_FOUND".to_string());
+                return resolved;
+            }
+        };
+
+        resolved.owner_id = Some(token.owner_id);
+        resolved.expires_at = Some(U64(self.nft_token_expires(token_id.clone())));
+        resolved.status = self.internal_token_status(&token_id);
+        if resolved.status != Some(NameStatus::Active) {
+            resolved.error = Some("NAME_EXPIRED".to_string());
+            return resolved;
+        }
+        //the records stored here aren't the ones the name resolves to
+        resolved.resolver_id = self.resolvers_by_token_id.get(&token_id);
+        if resolved.resolver_id.is_some() {
+            resolved.error = Some("USES_CUSTOM_RESOLVER".to_string());
+            return resolved;
+        }
+
+        if let Some(addresses) = self.addresses_by_token_id.get(&token_id) {
+            for network in query.networks {
+                if let Some(address) = addresses.get(&network) {
+                    resolved.addresses.insert(network, address.clone());
+                }
+            }
+        }
+        resolved
+    }
+}
+
+#[near_bindgen]
+impl NameResolver for Contract {
+    //resolves a record from the records stored in this contract
+    fn resolve(&self, name: TokenId, key: String) -> Option<String> {
+        if let Some(network) = key.strip_prefix(ADDRESS_KEY_PREFIX) {
+            self.get_address(name, network.to_string())
+        } else if let Some(text_key) = key.strip_prefix(TEXT_KEY_PREFIX) {
+            self.get_text(name, text_key.to_string())
+        } else if key == CONTENTHASH_KEY {
+            self.get_contenthash(name).map(|contenthash| contenthash.to_uri())
+        } else {
+            None
+        }
+    }
+}
+
+#[near_bindgen]
+impl ResolverCallback for Contract {
+    //a resolver that fails or returns something other than an optional string resolves to nothing
+    #[private]
+    fn on_resolve_via_resolver(&self) -> Option<String> {
+        match env::promise_result(0) {
+            PromiseResult::Successful(value) => near_sdk::serde_json::from_slice::<Option<String>>(&value).ok().flatten(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::*;
+
+/// Registration and renewal revenue the contract owner can withdraw. Everything else the
+/// contract holds pays for its storage or belongs to users, e.g. storage balances.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct RevenueLedger {
+    //payments net of referral rewards
+    pub accrued: Balance,
+    pub withdrawn: Balance,
+    //referral rewards credited but not claimed yet
+    pub referral_owed: Balance,
+    //NEAR held for users: storage balances, commitment deposits and USD payments waiting on the oracle
+    pub user_funds: Balance,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonRevenue {
+    //all registration and renewal revenue so far
+    pub total: U128,
+    pub withdrawn: U128,
+    //what `withdraw` can currently transfer
+    pub available: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn withdraw(&mut self, receiver: AccountId, amount: U128) -> Promise {
+        assert_one_yocto();
+        self.assert_only_owner();
+
+        let available = self.internal_withdrawable_revenue();
+        assert!(amount.0 <= available, "WITHDRAW_EXCEEDS_AVAILABLE_REVENUE: {} available", available);
+        self.revenue.withdrawn += amount.0;
+
+        Promise::new(receiver).transfer(amount.into())
+    }
+
+    pub fn get_revenue(&self) -> JsonRevenue {
+        JsonRevenue {
+            total: U128(self.revenue.accrued),
+            withdrawn: U128(self.revenue.withdrawn),
+            available: U128(self.internal_withdrawable_revenue()),
+        }
+    }
+}
+
+impl Contract {
+    //books a payment, less the referrer's share when one was given
+    pub(crate) fn internal_accrue_revenue(&mut self, payment: Balance, referrer: Option<&AccountId>) {
+        let reward = match referrer {
+            Some(referrer) => self.internal_credit_referrer(referrer, payment),
+            None => 0,
+        };
+        self.revenue.accrued += payment - reward;
+    }
+
+    //NEAR the contract received that still belongs to a user and must never be withdrawn as revenue
+    pub(crate) fn internal_hold_user_funds(&mut self, amount: Balance) {
+        self.revenue.user_funds += amount;
+    }
+
+    //NEAR held for a user was refunded to them or spent on their behalf
+    pub(crate) fn internal_release_user_funds(&mut self, amount: Balance) {
+        self.revenue.user_funds = self.revenue.user_funds.saturating_sub(amount);
+    }
+
+    //revenue not withdrawn yet, as long as the contract keeps enough to pay for its storage, unclaimed
+    //referral rewards and the NEAR it holds for users
+    fn internal_withdrawable_revenue(&self) -> Balance {
+        let storage_cost = env::storage_byte_cost() * Balance::from(env::storage_usage());
+        let unlocked_balance = env::account_balance()
+            .saturating_sub(storage_cost)
+            .saturating_sub(self.revenue.referral_owed)
+            .saturating_sub(self.revenue.user_funds);
+
+        (self.revenue.accrued - self.revenue.withdrawn).min(unlocked_balance)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::*;
+
+/// A name chosen as the primary name of an address. It only resolves while the name is
+/// active, still owned by `owner_id` and still records the address, so letting the name
+/// expire or removing the record unsets it. Transferring or removing the name deletes it.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PrimaryName {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    //make the name the primary name of the address it records for the network.
+    //a NEAR address claims its primary name itself. Addresses on other networks can't prove control here,
+    //so the name owner claims them, and only while no other name holds a valid claim on the address
+    #[payable]
+    pub fn set_primary_name(&mut self, token_id: TokenId, network: ChainKey) {
+        let initial_storage_usage = env::storage_usage();
+        let token_id = normalize_token_id(&token_id);
+        self.assert_token_expires(&token_id);
+
+        let address = self.addresses_by_token_id
+            .get(&token_id)
+            .and_then(|addresses| addresses.get(&network).cloned())
+            .expect("ADDRESS_NOT_RECORDED");
+
+        if is_near_chain(&network) {
+            assert_eq!(env::predecessor_account_id().to_string(), address, "ONLY_RECORDED_ADDRESS");
+        } else {
+            self.assert_token_owner(&token_id);
+            let current = self.get_primary_name(network.clone(), address.clone());
+            assert!(
+                current.is_none() || current.as_ref() == Some(&token_id),
+                "PRIMARY_NAME_CLAIMED: {}",
+                current.unwrap_or_default()
+            );
+        }
+
+        let owner_id = self.tokens_by_id.get(&token_id).expect("TOKEN_NOT_FOUND").owner_id;
+        self.primary_names.insert(&reverse_key(&network, &address), &PrimaryName { token_id, owner_id });
+
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    //clear the primary name of an address. Allowed for the owner of the name it points to and, on NEAR, the account itself
+    #[payable]
+    pub fn unset_primary_name(&mut self, network: ChainKey, address: String) {
+        let initial_storage_usage = env::storage_usage();
+        assert_one_yocto();
+        let key = reverse_key(&network, &address);
+        let primary_name = self.primary_names.get(&key).expect("PRIMARY_NAME_NOT_FOUND");
+
+        let caller = env::predecessor_account_id();
+        let is_address = is_near_chain(&network) && caller.to_string() == address;
+        let is_owner = self.tokens_by_id
+            .get(&primary_name.token_id)
+            .is_some_and(|token| token.owner_id == caller);
+        assert!(is_address || is_owner, "ONLY_RECORDED_ADDRESS_OR_NFT_OWNER");
+
+        self.primary_names.remove(&key);
+
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    pub fn get_primary_name(&self, network: ChainKey, address: String) -> Option<TokenId> {
+        let primary_name = self.primary_names.get(&reverse_key(&network, &address))?;
+
+        let token = self.tokens_by_id.get(&primary_name.token_id)?;
+        if token.owner_id != primary_name.owner_id || self.is_token_expires(&primary_name.token_id) {
+            return None;
+        }
+
+        let recorded = self.addresses_by_token_id
+            .get(&primary_name.token_id)
+            .and_then(|addresses| addresses.get(&network).cloned());
+        if recorded.as_ref() != Some(&address) {
+            return None;
+        }
+
+        Some(primary_name.token_id)
+    }
+
+    pub fn get_primary_name_for_account(&self, account_id: AccountId) -> Option<TokenId> {
+        self.get_primary_name(Network::NEAR.to_string(), account_id.to_string())
+    }
+}
+
+impl Contract {
+    //deletes the primary names that point at the name through the addresses it records, called
+    //before it changes hands or is removed so a later owner never inherits them
+    pub(crate) fn internal_remove_primary_names(&mut self, token_id: &TokenId) {
+        let addresses = match self.addresses_by_token_id.get(token_id) {
+            Some(addresses) => addresses,
+            None => return,
+        };
+        for (network, address) in addresses {
+            let key = reverse_key(&network, &address);
+            if self.primary_names.get(&key).is_some_and(|primary_name| primary_name.token_id == *token_id) {
+                self.primary_names.remove(&key);
+            }
+        }
+    }
+}
+
+fn is_near_chain(network: &ChainKey) -> bool {
+    *network == Network::NEAR.to_string()
+}
+
+fn reverse_key(network: &ChainKey, address: &str) -> String {
+    format!("{}:{}", network, address)
+}
@@ -1,20 +1,240 @@
 use crate::*;
 
+//longest account id NEAR allows, used to size a storage registration
+const MAX_ACCOUNT_ID_LENGTH: u64 = 64;
+//collection prefix and borsh length overhead of a storage balance entry
+const STORAGE_ACCOUNT_OVERHEAD: u64 = 40;
+
+/// NEP-145 storage balance of an account. `total` is what the account deposited and
+/// has not spent on storage yet, `available` is the part of it that can be withdrawn.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
 #[near_bindgen]
 impl Contract {
+    //deposit NEAR to pay for the storage of records and approvals without attaching it to every call
     #[payable]
-    pub fn storage_deposit(&mut self) -> (Balance, u128) {
-        let deposit_amount = env::attached_deposit();
-        let storage_used = env::storage_usage();
-        let storage_balance = env::storage_byte_cost() * Balance::from(storage_used);
-        (deposit_amount, storage_balance)
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let mut amount = env::attached_deposit();
+        let min_balance = storage_balance_min();
+
+        let balance = match self.storage_balances.get(&account_id) {
+            Some(balance) => {
+                if registration_only.unwrap_or(false) {
+                    //already registered, so nothing is needed
+                    if amount > 0 {
+                        Promise::new(env::predecessor_account_id()).transfer(amount);
+                    }
+                    return self.storage_balance_of(account_id).unwrap();
+                }
+                balance + amount
+            }
+            None => {
+                assert!(
+                    amount >= min_balance,
+                    "The attached deposit is less than the minimum storage balance {}",
+                    min_balance
+                );
+                if registration_only.unwrap_or(false) {
+                    let refund = amount - min_balance;
+                    if refund > 0 {
+                        Promise::new(env::predecessor_account_id()).transfer(refund);
+                    }
+                    amount = min_balance;
+                }
+                amount
+            }
+        };
+
+        self.internal_set_storage_balance(&account_id, balance);
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let balance = self.storage_balances.get(&account_id).expect("STORAGE_NOT_REGISTERED");
+        let available = balance.saturating_sub(storage_balance_min());
+
+        let amount = amount.map_or(available, |amount| amount.0);
+        assert!(amount <= available, "STORAGE_WITHDRAW_EXCEEDS_AVAILABLE: {} available", available);
+
+        self.internal_set_storage_balance(&account_id, balance - amount);
+        if amount > 0 {
+            Promise::new(account_id.clone()).transfer(amount);
+        }
+        self.storage_balance_of(account_id).unwrap()
     }
 
+    //close the caller's storage account and refund all of it. Storage already paid for records
+    //stays with them, so forcing an unregistration that would remove them isn't supported
     #[payable]
-    pub fn withdraw(&mut self, receiver: AccountId, amount: U128) -> Promise {
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
         assert_one_yocto();
-        self.assert_only_owner();
+        assert!(!force.unwrap_or(false), "FORCE_UNREGISTER_NOT_SUPPORTED");
+        let account_id = env::predecessor_account_id();
+        match self.storage_balances.remove(&account_id) {
+            Some(balance) => {
+                self.internal_release_user_funds(balance);
+                if balance > 0 {
+                    Promise::new(account_id).transfer(balance);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_balances.get(&account_id).map(|balance| StorageBalance {
+            total: U128(balance),
+            available: U128(balance.saturating_sub(storage_balance_min())),
+        })
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128(storage_balance_min()),
+            max: None,
+        }
+    }
+}
+
+//what it costs to store the storage balance of the longest possible account id
+pub(crate) fn storage_balance_min() -> Balance {
+    env::storage_byte_cost() * Balance::from(MAX_ACCOUNT_ID_LENGTH + 16 + STORAGE_ACCOUNT_OVERHEAD)
+}
+
+impl Contract {
+    //stores the storage balance of an account, keeping the NEAR held for users in step with it
+    pub(crate) fn internal_set_storage_balance(&mut self, account_id: &AccountId, balance: Balance) {
+        let previous = self.storage_balances.insert(account_id, &balance).unwrap_or(0);
+        self.internal_release_user_funds(previous);
+        self.internal_hold_user_funds(balance);
+    }
+
+    //pays for storage the caller's call used, from the attached deposit first and their storage
+    //balance for the rest. Whatever is left of the attached deposit is refunded
+    pub(crate) fn internal_charge_storage(&mut self, storage_used: u64) {
+        let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
+        let attached_deposit = env::attached_deposit();
+
+        if required_cost <= attached_deposit {
+            let refund = attached_deposit - required_cost;
+            //if the refund is greater than 1 yocto NEAR, we refund the predecessor that amount
+            if refund > 1 {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+            return;
+        }
+
+        let account_id = env::predecessor_account_id();
+        let shortfall = required_cost - attached_deposit;
+        let balance = self.storage_balances.get(&account_id).unwrap_or(0);
+        assert!(
+            shortfall <= balance.saturating_sub(storage_balance_min()),
+            "Must attach {} yoctoNEAR or deposit it with storage_deposit to cover storage",
+            required_cost,
+        );
+        self.internal_set_storage_balance(&account_id, balance - shortfall);
+    }
+
+    //charges the caller for the storage their call added since `initial_storage_usage`, or
+    //credits them with the storage it freed
+    pub(crate) fn internal_settle_storage(&mut self, initial_storage_usage: u64) {
+        let current_storage_usage = env::storage_usage();
+        if current_storage_usage >= initial_storage_usage {
+            self.internal_charge_storage(current_storage_usage - initial_storage_usage);
+            return;
+        }
+
+        let released = env::storage_byte_cost() * Balance::from(initial_storage_usage - current_storage_usage)
+            + env::attached_deposit();
+        self.internal_credit_storage(&env::predecessor_account_id(), released);
+    }
+
+    //settles a call that changed the records of a name. Records outlive owners and managers, so the
+    //storage the call added is remembered as paid by the caller, and storage it freed goes back to
+    //whoever paid for it, the caller first
+    pub(crate) fn internal_settle_record_storage(&mut self, token_id: &TokenId, initial_storage_usage: u64) {
+        let account_id = env::predecessor_account_id();
+        let mut payers = self.record_storage_payers.get(token_id).unwrap_or_default();
+
+        if env::storage_usage() >= initial_storage_usage {
+            //a new payer's entry is part of what they pay for
+            if !payers.contains_key(&account_id) {
+                payers.insert(account_id.clone(), 0);
+                self.record_storage_payers.insert(token_id, &payers);
+            }
+            let storage_used = env::storage_usage() - initial_storage_usage;
+            self.internal_charge_storage(storage_used);
+            *payers.get_mut(&account_id).unwrap() += storage_used;
+            self.record_storage_payers.insert(token_id, &payers);
+            return;
+        }
+
+        let mut unrefunded = initial_storage_usage - env::storage_usage();
+        let mut caller_refund = env::attached_deposit();
+        let mut payer_ids = vec![account_id.clone()];
+        payer_ids.extend(payers.keys().filter(|payer_id| **payer_id != account_id).cloned());
+        for payer_id in payer_ids {
+            let paid = payers.get(&payer_id).copied().unwrap_or(0);
+            let refunded = unrefunded.min(paid);
+            unrefunded -= refunded;
+            if refunded == paid {
+                payers.remove(&payer_id);
+            } else {
+                payers.insert(payer_id.clone(), paid - refunded);
+            }
+
+            let amount = env::storage_byte_cost() * Balance::from(refunded);
+            if payer_id == account_id {
+                caller_refund += amount;
+            } else if amount > 0 {
+                self.internal_credit_storage(&payer_id, amount);
+            }
+        }
+        //storage no one is known to have paid for, e.g. records from before payers were tracked,
+        //goes to the caller like it always has
+        caller_refund += env::storage_byte_cost() * Balance::from(unrefunded);
+        self.internal_credit_storage(&account_id, caller_refund);
+
+        if payers.is_empty() {
+            self.record_storage_payers.remove(token_id);
+        } else {
+            self.record_storage_payers.insert(token_id, &payers);
+        }
+    }
+
+    //gives every payer of a name's records back what they paid, once the records are gone
+    pub(crate) fn internal_refund_record_storage(&mut self, token_id: &TokenId) {
+        for (payer_id, paid) in self.record_storage_payers.remove(token_id).unwrap_or_default() {
+            self.internal_credit_storage(&payer_id, env::storage_byte_cost() * Balance::from(paid));
+        }
+    }
 
-        Promise::new(receiver).transfer(amount.into())
+    //adds to the account's storage balance, or sends it the amount if it has none
+    fn internal_credit_storage(&mut self, account_id: &AccountId, amount: Balance) {
+        match self.storage_balances.get(account_id) {
+            Some(balance) => self.internal_set_storage_balance(account_id, balance + amount),
+            None => {
+                if amount > 0 {
+                    Promise::new(account_id.clone()).transfer(amount);
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
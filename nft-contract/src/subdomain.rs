@@ -0,0 +1,93 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    //create "label.parent_id" for the given owner. Only the owner of the parent name can create subdomains,
+    //and a subdomain never outlives its parent: its expiry is capped at the parent's expiry
+    #[payable]
+    pub fn create_subdomain(
+        &mut self,
+        parent_id: TokenId,
+        label: String,
+        owner_id: AccountId,
+        metadata: TokenMetadata,
+        expires_at: Option<U64>,
+    ) -> u64 {
+        let parent_id = normalize_token_id(&parent_id);
+        self.assert_token_owner(&parent_id);
+        self.assert_token_expires(&parent_id);
+
+        let domain = assert_valid_domain(&format!("{}.{}", label, parent_id));
+        let token_id = domain.to_string();
+
+        //an expired subdomain can be handed out again by the parent owner straight away
+        if let Some(status) = self.internal_token_status(&token_id) {
+            assert!(status != NameStatus::Active, "Token already exists");
+            self.internal_release_token(&token_id);
+        }
+        //the released subdomain's records were already refunded to whoever paid for them
+        let initial_storage_usage = env::storage_usage();
+
+        self.internal_mint(&domain, metadata, owner_id, None, 0);
+        let expires_at = self.internal_set_subdomain_expires(&token_id, &parent_id, expires_at);
+
+        self.internal_settle_storage(initial_storage_usage);
+
+        expires_at
+    }
+
+    //move the subdomain's expiry, e.g. after the parent has been renewed. It is still capped at the parent's expiry
+    pub fn extend_subdomain(&mut self, token_id: TokenId, expires_at: Option<U64>) -> u64 {
+        let (token_id, parent_id) = self.assert_subdomain_parent_owner(&token_id);
+        self.assert_token_expires(&parent_id);
+
+        let expires_at = self.internal_set_subdomain_expires(&token_id, &parent_id, expires_at);
+        self.internal_log_name_renewed(&token_id, expires_at);
+        expires_at
+    }
+
+    //take the subdomain back from its current owner and give it to the owner of the parent name
+    #[payable]
+    pub fn reclaim_subdomain(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        let (token_id, _) = self.assert_subdomain_parent_owner(&token_id);
+
+        let owner_id = self.tokens_by_id.get(&token_id).expect("TOKEN_NOT_FOUND").owner_id;
+        //transfer on behalf of the current owner, which also resets its approvals
+        let previous_token = self.internal_transfer(
+            &owner_id,
+            &env::predecessor_account_id(),
+            &token_id,
+            None,
+            Some("reclaimed by parent owner".to_string()),
+        );
+
+        refund_approved_account_ids(previous_token.owner_id, &previous_token.approved_account_ids);
+    }
+}
+
+impl Contract {
+    //make sure the caller owns the parent of the given subdomain, returning the canonical subdomain and parent IDs
+    fn assert_subdomain_parent_owner(&self, token_id: &TokenId) -> (TokenId, TokenId) {
+        let domain = assert_valid_domain(token_id);
+        let parent = domain.parent().expect("NOT_A_SUBDOMAIN");
+        let parent_id = parent.to_string();
+        self.assert_token_owner(&parent_id);
+
+        (domain.to_string(), parent_id)
+    }
+
+    fn internal_set_subdomain_expires(&mut self, token_id: &TokenId, parent_id: &TokenId, expires_at: Option<U64>) -> u64 {
+        let parent_expires_at = self.nft_token_expires(parent_id.clone());
+        let expires_at = expires_at.map_or(parent_expires_at, |expires_at| expires_at.0.min(parent_expires_at));
+
+        let token_metadata = self.token_metadata_by_id.get(token_id).expect("NFT_NOT_FOUND");
+        self.token_metadata_by_id.insert(token_id, &TokenMetadata {
+            expires_at: Some(expires_at),
+            updated_at: Some(env::block_timestamp()),
+            ..token_metadata
+        });
+
+        expires_at
+    }
+}
@@ -0,0 +1,121 @@
+use crate::*;
+
+/// Longest key of a text record, e.g. "com.twitter".
+pub const MAX_TEXT_KEY_LENGTH: usize = 64;
+/// Longest value of a text record.
+pub const MAX_TEXT_VALUE_LENGTH: usize = 1024;
+/// Most text records a single name can hold.
+pub const MAX_TEXT_RECORDS: usize = 32;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TextRecordInput {
+    pub key: String,
+    pub value: String,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_text_records(&mut self, token_id: TokenId, records: Vec<TextRecordInput>) {
+        let initial_storage_usage = env::storage_usage();
+        // assert if function caller is not the owner or manager
+        self.assert_token_owner_or_manager(&token_id);
+
+        self.assert_token_expires(&token_id);
+
+        let mut text_records = self.text_records_by_token_id.get(&token_id).unwrap_or_default();
+
+        let mut text_set_logs = Vec::new();
+        for record in records.iter() {
+            assert_valid_text_record(record);
+            text_records.insert(record.key.clone(), record.value.clone());
+            text_set_logs.push(TextSetLog {
+                token_id: token_id.clone(),
+                key: record.key.clone(),
+                value: record.value.clone(),
+            });
+        }
+        assert!(
+            text_records.len() <= MAX_TEXT_RECORDS,
+            "TOO_MANY_TEXT_RECORDS: at most {} per name",
+            MAX_TEXT_RECORDS
+        );
+
+        self.text_records_by_token_id.insert(&token_id, &text_records);
+        if !text_set_logs.is_empty() {
+            log_name_service_event(EventLogVariant::TextSet(text_set_logs));
+        }
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+
+    pub fn get_text(&self, token_id: TokenId, key: String) -> Option<String> {
+        let token_id = normalize_token_id(&token_id);
+        if self.is_token_expires(&token_id) {
+            return None;
+        }
+
+        self.text_records_by_token_id
+            .get(&token_id)
+            .and_then(|text_records| text_records.get(&key).cloned())
+    }
+
+    pub fn get_text_records(&self, token_id: TokenId) -> HashMap<String, String> {
+        let token_id = normalize_token_id(&token_id);
+        if self.is_token_expires(&token_id) {
+            return HashMap::new();
+        }
+
+        self.text_records_by_token_id.get(&token_id).unwrap_or_default()
+    }
+
+    #[payable]
+    pub fn remove_text_records(&mut self, token_id: TokenId, keys: Vec<String>) {
+        let initial_storage_usage = env::storage_usage();
+        assert_one_yocto();
+        if self.is_token_expires(&token_id) {
+            self.assert_only_owner();
+        } else {
+            self.assert_token_owner_or_manager(&token_id);
+        }
+
+        let mut text_records = self.text_records_by_token_id.get(&token_id).expect("NO_TEXT_RECORDS_TOKEN");
+        let mut text_removed_logs = Vec::new();
+        for key in keys.iter() {
+            if text_records.remove(key).is_some() {
+                text_removed_logs.push(TextRemovedLog { token_id: token_id.clone(), key: key.clone() });
+            }
+        }
+
+        if text_records.is_empty() {
+            self.text_records_by_token_id.remove(&token_id);
+        } else {
+            self.text_records_by_token_id.insert(&token_id, &text_records);
+        }
+        if !text_removed_logs.is_empty() {
+            log_name_service_event(EventLogVariant::TextRemoved(text_removed_logs));
+        }
+
+        self.internal_settle_record_storage(&token_id, initial_storage_usage);
+    }
+}
+
+fn assert_valid_text_record(record: &TextRecordInput) {
+    assert!(
+        !record.key.is_empty() && record.key.len() <= MAX_TEXT_KEY_LENGTH,
+        "INVALID_TEXT_KEY: must be between 1 and {} bytes",
+        MAX_TEXT_KEY_LENGTH
+    );
+    assert!(
+        record.key.chars().all(|c| c.is_ascii_graphic()),
+        "INVALID_TEXT_KEY: {:?} must only contain printable ASCII characters",
+        record.key
+    );
+    assert!(
+        record.value.len() <= MAX_TEXT_VALUE_LENGTH,
+        "INVALID_TEXT_VALUE: {} is longer than {} bytes",
+        record.key,
+        MAX_TEXT_VALUE_LENGTH
+    );
+}
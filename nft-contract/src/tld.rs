@@ -0,0 +1,155 @@
+use crate::*;
+
+/// The top-level domain every deployment starts with.
+pub const DEFAULT_TLD: &str = "btc";
+/// Media shown for names under the default top-level domain.
+pub const DEFAULT_TLD_MEDIA: &str = "https://bafybeie4sb64l2tevfwrt6dtr7z32seliyybw4g5nanxt4dbdokn2fyk6m.ipfs.dweb.link/dnet.png";
+
+/// Owner-managed settings of a top-level domain.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TldConfig {
+    //whether new names can be registered under this TLD. Existing names can still be renewed
+    pub registration_open: bool,
+    //longest label that can be registered, e.g. 63 allows "manhng.btc" with up to 63 characters before the dot
+    pub max_label_length: u8,
+    //media every name under this TLD is minted with
+    pub default_media: Option<String>,
+    //pricing for this TLD. If not set, the contract-wide price tiers are used
+    pub price_tiers: Option<PriceTiers>,
+}
+
+impl Default for TldConfig {
+    fn default() -> Self {
+        Self {
+            registration_open: true,
+            max_label_length: MAX_LABEL_LENGTH as u8,
+            default_media: Some(DEFAULT_TLD_MEDIA.to_string()),
+            price_tiers: None,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Tld {
+    pub config: TldConfig,
+    //number of names currently registered directly under this TLD, subdomains excluded
+    pub registrations: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonTld {
+    pub tld: String,
+    #[serde(flatten)]
+    pub config: TldConfig,
+    pub registrations: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn add_tld(&mut self, tld: String, config: TldConfig) {
+        self.assert_only_owner();
+        let tld = normalize_token_id(&tld);
+        validate_tld(&tld).unwrap_or_else(|reason| panic!("INVALID_TLD: {}", reason));
+        assert!(self.tlds.get(&tld).is_none(), "TLD_ALREADY_EXISTS");
+        assert_valid_tld_config(&config);
+
+        let has_price_tiers = config.price_tiers.is_some();
+        self.tlds.insert(&tld, &Tld { config, registrations: 0 });
+        if has_price_tiers {
+            log_price_changed("tld", Some(tld), None);
+        }
+    }
+
+    pub fn update_tld(&mut self, tld: String, config: TldConfig) {
+        self.assert_only_owner();
+        let tld = normalize_token_id(&tld);
+        let mut stored = self.tlds.get(&tld).expect("TLD_NOT_FOUND");
+        assert_valid_tld_config(&config);
+
+        let price_changed = stored.config.price_tiers != config.price_tiers;
+        stored.config = config;
+        self.tlds.insert(&tld, &stored);
+        if price_changed {
+            log_price_changed("tld", Some(tld), None);
+        }
+    }
+
+    //close a TLD for new registrations. Names already registered under it keep resolving and can be renewed
+    pub fn disable_tld(&mut self, tld: String) {
+        self.assert_only_owner();
+        let tld = normalize_token_id(&tld);
+        let mut stored = self.tlds.get(&tld).expect("TLD_NOT_FOUND");
+
+        stored.config.registration_open = false;
+        self.tlds.insert(&tld, &stored);
+    }
+
+    pub fn get_tld(&self, tld: String) -> Option<JsonTld> {
+        let tld = normalize_token_id(&tld);
+        self.tlds.get(&tld).map(|stored| JsonTld {
+            tld,
+            config: stored.config,
+            registrations: U64(stored.registrations),
+        })
+    }
+
+    pub fn get_tlds(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonTld> {
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.tlds.iter()
+            .skip(start as usize)
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(tld, stored)| JsonTld {
+                tld,
+                config: stored.config,
+                registrations: U64(stored.registrations),
+            })
+            .collect()
+    }
+}
+
+fn assert_valid_tld_config(config: &TldConfig) {
+    assert!(
+        config.max_label_length > 0 && usize::from(config.max_label_length) <= MAX_LABEL_LENGTH,
+        "max_label_length must be between 1 and {}",
+        MAX_LABEL_LENGTH
+    );
+    if let Some(price_tiers) = config.price_tiers.as_ref() {
+        price_tiers.assert_valid();
+    }
+}
+
+impl Contract {
+    //make sure a name can be registered under its TLD, returning the TLD settings
+    pub(crate) fn assert_tld_open(&self, domain: &DomainName) -> TldConfig {
+        self.internal_check_tld_open(domain).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub(crate) fn internal_check_tld_open(&self, domain: &DomainName) -> Result<TldConfig, String> {
+        let tld = self.tlds.get(&domain.tld).ok_or_else(|| format!("TLD_NOT_FOUND: {}", domain.tld))?;
+        if !tld.config.registration_open {
+            return Err(format!("TLD_CLOSED: {}", domain.tld));
+        }
+        if domain.name_label().len() > usize::from(tld.config.max_label_length) {
+            return Err(format!("INVALID_NAME: label is longer than {} characters", tld.config.max_label_length));
+        }
+        Ok(tld.config)
+    }
+
+    pub(crate) fn internal_update_tld_registrations(&mut self, token_id: &TokenId, registered: bool) {
+        let domain = match DomainName::parse(token_id) {
+            Ok(domain) if !domain.is_subdomain() => domain,
+            _ => return,
+        };
+        if let Some(mut tld) = self.tlds.get(&domain.tld) {
+            tld.registrations = if registered {
+                tld.registrations + 1
+            } else {
+                tld.registrations.saturating_sub(1)
+            };
+            self.tlds.insert(&domain.tld, &tld);
+        }
+    }
+}
@@ -1,47 +1,125 @@
 use crate::*;
 
+/// Lifecycle of a registered name: renewable by anyone while active, only by the
+/// previous owner during the grace period, and open for re-registration once released.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum NameStatus {
+    Active,
+    GracePeriod,
+    Released,
+}
+
 #[near_bindgen]
 impl Contract {
 
     pub fn nft_token_expires(&self, token_id: String) -> u64 {
-        let expires = if let Some(token_metadata) = self.token_metadata_by_id.get(&token_id) {
-            token_metadata.expires_at.unwrap_or(0)
-        } else {
-            0
-        };
-        expires
+        self.internal_expires_at(&token_id).unwrap_or(0)
     }
 
     pub fn is_token_expires(&self, token_id: &TokenId) -> bool {
-        let token_option = self.token_metadata_by_id.get(&token_id);
-        match token_option {
-            Some(token) => {
-                is_expires(&token.expires_at)
+        match self.internal_expires_at(token_id) {
+            Some(expires_at) => {
+                is_expires(&Some(expires_at))
             }
             None => true,
         }
         
     }
 
+    pub fn nft_token_status(&self, token_id: TokenId) -> Option<NameStatus> {
+        self.internal_token_status(&normalize_token_id(&token_id))
+    }
+
     #[payable]
-    pub fn extend_token(&mut self, token_id: String) -> (u64, u128) {
+    pub fn extend_token(&mut self, token_id: String, referrer: Option<AccountId>) -> (u64, u128) {
+        let domain = assert_valid_domain(&token_id);
+        self.assert_renewable(&domain, &env::predecessor_account_id());
+        assert_valid_referrer(referrer.as_ref());
+        let token_id = domain.to_string();
+        let price_per_year = self.internal_price_per_year(&domain);
         let deposit_balance = env::attached_deposit();
         let account_id = env::predecessor_account_id();
-        assert!(deposit_balance >= self.price_per_year, "EXTEND_AT_LEAST_ONE_YEAR");
-        let extend_years = u64::try_from(deposit_balance / self.price_per_year).ok().unwrap();
-        let change = deposit_balance % self.price_per_year;
+        assert!(deposit_balance >= price_per_year, "EXTEND_AT_LEAST_ONE_YEAR");
+        let extend_years = u64::try_from(deposit_balance / price_per_year).ok().unwrap();
+        let change = deposit_balance % price_per_year;
+        self.internal_accrue_revenue(deposit_balance - change, referrer.as_ref());
         let expires_at = self.internal_extend_token(&token_id, extend_years * ONE_YEAR_NANOSECOND);
+        self.internal_log_name_renewed(&token_id, expires_at);
         Promise::new(account_id).transfer(change);
         (expires_at, change)
     }
 }
 
 impl Contract {
+    pub(crate) fn assert_renewable(&self, domain: &DomainName, account_id: &AccountId) {
+        if let Err(err) = self.internal_check_renewable(domain, account_id) {
+            panic!("{}", err);
+        }
+    }
+
+    //subdomains follow their parent's expiry, and during the grace period only the owner can renew
+    pub(crate) fn internal_check_renewable(&self, domain: &DomainName, account_id: &AccountId) -> Result<(), &'static str> {
+        if domain.is_subdomain() {
+            return Err("SUBDOMAIN_EXPIRY_FOLLOWS_PARENT");
+        }
+        let token_id = domain.to_string();
+        match self.internal_token_status(&token_id) {
+            None => Err("NFT_NOT_FOUND"),
+            Some(NameStatus::Active) => Ok(()),
+            Some(NameStatus::GracePeriod) if self.tokens_by_id.get(&token_id).map(|token| token.owner_id) == Some(account_id.clone()) => Ok(()),
+            Some(NameStatus::GracePeriod) => Err("ONLY_NFT_OWNER"),
+            Some(NameStatus::Released) => Err("NAME_RELEASED"),
+        }
+    }
+
+    //when the name stops resolving. A subdomain also stops with its parent, and for good once it is orphaned
+    pub(crate) fn internal_expires_at(&self, token_id: &TokenId) -> Option<u64> {
+        let token_metadata = self.token_metadata_by_id.get(token_id)?;
+        let expires_at = token_metadata.expires_at.unwrap_or(0);
+        match parent_id(token_id) {
+            None => Some(expires_at),
+            Some(_) if self.internal_is_orphaned(token_id, &token_metadata) => Some(0),
+            Some(parent_id) => Some(expires_at.min(self.internal_expires_at(&parent_id).unwrap_or(0))),
+        }
+    }
+
+    //a subdomain is orphaned once its parent is removed or registered again, so whoever registers
+    //the parent next never finds the old subdomains under it
+    fn internal_is_orphaned(&self, token_id: &TokenId, token_metadata: &TokenMetadata) -> bool {
+        let parent_id = match parent_id(token_id) {
+            Some(parent_id) => parent_id,
+            None => return false,
+        };
+        match self.token_metadata_by_id.get(&parent_id) {
+            Some(parent_metadata) => {
+                parent_metadata.issued_at > token_metadata.issued_at
+                    || self.internal_is_orphaned(&parent_id, &parent_metadata)
+            }
+            None => true,
+        }
+    }
+
+    pub(crate) fn internal_token_status(&self, token_id: &TokenId) -> Option<NameStatus> {
+        let token_metadata = self.token_metadata_by_id.get(token_id)?;
+        if self.internal_is_orphaned(token_id, &token_metadata) {
+            return Some(NameStatus::Released);
+        }
+        let expires_at = self.internal_expires_at(token_id)?;
+        let now = env::block_timestamp();
+        if now <= expires_at {
+            Some(NameStatus::Active)
+        } else if now <= expires_at + GRACE_PERIOD_NANOSECOND {
+            Some(NameStatus::GracePeriod)
+        } else {
+            Some(NameStatus::Released)
+        }
+    }
+
     pub(crate) fn assert_token_expires(&self, token_id: &TokenId) {
         // check expires
-        let token_metadata = self.token_metadata_by_id.get(token_id).expect("TOKEN_NOT_FOUND");
-        let token_expires = token_metadata.expires_at;
-        assert_expires(&token_expires);
+        let token_expires = self.internal_expires_at(token_id).expect("TOKEN_NOT_FOUND");
+        assert_expires(&Some(token_expires));
     }
 
     pub(crate) fn internal_extend_token(&mut self, token_id: &TokenId, extend_ttl: u64) -> u64 {
@@ -57,4 +135,18 @@ impl Contract {
         self.token_metadata_by_id.insert(token_id, &new_metadata);
         new_expires_date.unwrap()
     }
+
+    pub(crate) fn internal_log_name_renewed(&self, token_id: &TokenId, expires_at: u64) {
+        let token = self.tokens_by_id.get(token_id).expect("TOKEN_NOT_FOUND");
+        log_name_service_event(EventLogVariant::NameRenewed(vec![NameRenewedLog {
+            token_id: token_id.to_string(),
+            owner_id: token.owner_id.to_string(),
+            expires_at: expires_at.to_string(),
+        }]));
+    }
+}
+
+//the name one level up, if this is a subdomain
+fn parent_id(token_id: &TokenId) -> Option<TokenId> {
+    DomainName::parse(token_id).ok()?.parent().map(|parent| parent.to_string())
 }
\ No newline at end of file
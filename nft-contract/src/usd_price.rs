@@ -0,0 +1,357 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_ORACLE: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_USD_CALLBACK: Gas = Gas(30_000_000_000_000);
+const NO_DEPOSIT: Balance = 0;
+//slippage tolerances are in basis points of the expected NEAR price
+pub const MAX_SLIPPAGE: u32 = 10_000;
+//more decimals than this could overflow converting cents to yoctoNEAR
+const MAX_QUOTE_DECIMALS: u8 = 12;
+
+/// Pricing in USD cents, converted to NEAR at the rate of the oracle contract when a name
+/// is registered or renewed with `nft_mint_usd` or `extend_token_usd`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsdPricing {
+    pub oracle_id: AccountId,
+    //yearly price of each label length, in USD cents
+    pub price_tiers: PriceTiers,
+    //oldest quote accepted, in nanoseconds
+    pub max_quote_age: U64,
+}
+
+/// Price of 1 NEAR in USD, `price / 10^decimals`, as of `timestamp` (nanoseconds).
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NearUsdQuote {
+    pub price: U128,
+    pub decimals: u8,
+    pub timestamp: U64,
+}
+
+/// Payment waiting for its quote. The caller accepts the quote if NEAR is worth at least
+/// `expected_price`, in the oracle's units, less `slippage` basis points.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsdPayment {
+    pub payer_id: AccountId,
+    pub deposit: U128,
+    pub expected_price: U128,
+    pub slippage: u32,
+    //partner credited with a share of the price, see `set_referral_rate`
+    pub referrer: Option<AccountId>,
+}
+
+/// Registration waiting for its quote. Same as the arguments of `nft_mint`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsdMint {
+    pub token_id: TokenId,
+    pub metadata: TokenMetadata,
+    pub receiver_id: AccountId,
+    pub perpetual_royalties: Option<HashMap<AccountId, u32>>,
+    pub years: Option<u32>,
+}
+
+//interface of the price oracle contract
+#[ext_contract(ext_price_oracle)]
+trait NearUsdOracle {
+    fn get_near_usd_price(&self) -> NearUsdQuote;
+}
+
+#[ext_contract(ext_usd_callback)]
+trait UsdPriceCallback {
+    fn on_usd_mint(&mut self, payment: UsdPayment, mint: UsdMint) -> Option<U64>;
+    fn on_usd_renew(&mut self, payment: UsdPayment, token_id: TokenId) -> Option<U64>;
+}
+
+pub trait UsdPriceCallback {
+    fn on_usd_mint(&mut self, payment: UsdPayment, mint: UsdMint) -> Option<U64>;
+    fn on_usd_renew(&mut self, payment: UsdPayment, token_id: TokenId) -> Option<U64>;
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_usd_pricing(&mut self, usd_pricing: UsdPricing) {
+        self.assert_only_owner();
+        let tiers = &usd_pricing.price_tiers;
+        for price in [tiers.one_two_chars, tiers.three_chars, tiers.four_chars, tiers.five_plus_chars] {
+            assert!(price.0 > 0, "INVALID_USD_PRICE");
+        }
+        assert!(usd_pricing.max_quote_age.0 > 0, "INVALID_MAX_QUOTE_AGE");
+        self.usd_pricing = Some(usd_pricing);
+        log_price_changed("usd_tiers", None, None);
+    }
+
+    pub fn remove_usd_pricing(&mut self) {
+        self.assert_only_owner();
+        if self.usd_pricing.take().is_some() {
+            log_price_changed("usd_tiers", None, None);
+        }
+    }
+
+    pub fn get_usd_pricing(&self) -> Option<UsdPricing> {
+        self.usd_pricing.clone()
+    }
+
+    //yearly price of a name in USD cents
+    pub fn nft_name_price_per_year_usd(&self, token_id: TokenId) -> U128 {
+        let domain = assert_valid_domain(&token_id);
+        U128(self.internal_usd_price_per_year(&domain))
+    }
+
+    //registers a name priced in USD, paid with the attached NEAR at the oracle's rate. `expected_price` is the
+    //NEAR/USD price the caller saw, in the oracle's units, and the registration is refunded if the oracle's price
+    //is more than `slippage` basis points below it. The deposit of the caller's commitment to the name, see `commit`,
+    //is added to the attached deposit, and whatever they covered beyond the price is refunded. Names under
+    //a TLD with its own tiers are priced in NEAR only
+    #[payable]
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_mint_usd(
+        &mut self,
+        token_id: TokenId,
+        metadata: TokenMetadata,
+        receiver_id: AccountId,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        years: Option<u32>,
+        expected_price: U128,
+        slippage: u32,
+        secret: String,
+        referrer: Option<AccountId>,
+    ) -> Promise {
+        let domain = assert_valid_domain(&token_id);
+        //fail before calling the oracle when the name can't be registered
+        if let Err(err) = self.internal_check_usd_registrable(&domain) {
+            panic!("{}", err);
+        }
+        assert!(years != Some(0), "MINT_AT_LEAST_ONE_YEAR");
+        assert!(
+            years.is_none_or(|years| expires_after(env::block_timestamp(), u64::from(years)).is_some()),
+            "INVALID_YEARS"
+        );
+        assert_expected_price(expected_price);
+        assert!(perpetual_royalties.as_ref().map_or(0, |royalties| royalties.len()) < 7, "Cannot add more than 6 perpetual royalty amounts");
+        assert!(slippage <= MAX_SLIPPAGE, "INVALID_SLIPPAGE");
+        assert_valid_referrer(referrer.as_ref());
+        let deposit = env::attached_deposit()
+            + self.internal_take_commitment(&env::predecessor_account_id(), &domain, &receiver_id, &secret);
+
+        let mint = UsdMint { token_id: domain.to_string(), metadata, receiver_id, perpetual_royalties, years };
+        self.internal_hold_user_funds(deposit);
+        self.internal_fetch_quote().then(ext_usd_callback::on_usd_mint(
+            UsdPayment { payer_id: env::predecessor_account_id(), deposit: U128(deposit), expected_price, slippage, referrer },
+            mint,
+            env::current_account_id(), //contract account to make the call to
+            NO_DEPOSIT, //attached deposit
+            GAS_FOR_USD_CALLBACK, //GAS attached to the call
+        ))
+    }
+
+    //renews a name priced in USD for as many whole years as the attached NEAR covers at the oracle's rate.
+    //`expected_price`, `slippage` and `referrer` work as in `nft_mint_usd`
+    #[payable]
+    pub fn extend_token_usd(&mut self, token_id: TokenId, expected_price: U128, slippage: u32, referrer: Option<AccountId>) -> Promise {
+        let domain = assert_valid_domain(&token_id);
+        self.assert_renewable(&domain, &env::predecessor_account_id());
+        assert!(self.premium_prices.get(&domain.to_string()).is_none(), "PREMIUM_NAME_NEAR_ONLY");
+        assert!(!self.internal_has_tld_prices(&domain), "TLD_PRICED_IN_NEAR_ONLY: {}", domain.tld);
+        assert_expected_price(expected_price);
+        assert!(slippage <= MAX_SLIPPAGE, "INVALID_SLIPPAGE");
+        assert_valid_referrer(referrer.as_ref());
+        let deposit = env::attached_deposit();
+        assert!(deposit > 0, "INVALID_DEPOSIT");
+        self.internal_hold_user_funds(deposit);
+
+        self.internal_fetch_quote().then(ext_usd_callback::on_usd_renew(
+            UsdPayment { payer_id: env::predecessor_account_id(), deposit: U128(deposit), expected_price, slippage, referrer },
+            domain.to_string(),
+            env::current_account_id(), //contract account to make the call to
+            NO_DEPOSIT, //attached deposit
+            GAS_FOR_USD_CALLBACK, //GAS attached to the call
+        ))
+    }
+}
+
+impl Contract {
+    fn internal_usd_pricing(&self) -> UsdPricing {
+        self.usd_pricing.clone().expect("USD_PRICING_DISABLED")
+    }
+
+    fn internal_usd_price_per_year(&self, domain: &DomainName) -> Balance {
+        self.internal_usd_pricing().price_tiers.price_for_label(domain.name_label())
+    }
+
+    fn internal_fetch_quote(&self) -> Promise {
+        ext_price_oracle::get_near_usd_price(
+            self.internal_usd_pricing().oracle_id, //contract account to make the call to
+            NO_DEPOSIT, //attached deposit
+            GAS_FOR_ORACLE, //attached GAS
+        )
+    }
+
+    //premium names and the release auction are priced in NEAR only
+    fn internal_check_usd_registrable(&self, domain: &DomainName) -> Result<(), String> {
+        if domain.is_subdomain() {
+            return Err("INVALID_NAME: subdomains cannot be minted directly".to_string());
+        }
+        if self.internal_presale_for(domain).is_some() {
+            return Err(format!("PRESALE_ONLY: {}", domain.tld));
+        }
+        self.internal_check_tld_open(domain)?;
+        let token_id = domain.to_string();
+        if self.premium_prices.get(&token_id).is_some() {
+            return Err("PREMIUM_NAME_NEAR_ONLY".to_string());
+        }
+        if self.internal_has_tld_prices(domain) {
+            return Err(format!("TLD_PRICED_IN_NEAR_ONLY: {}", domain.tld));
+        }
+        match self.internal_token_status(&token_id) {
+            None => Ok(()),
+            Some(NameStatus::Released) if self.internal_release_premium(&token_id) == 0 => Ok(()),
+            Some(NameStatus::Released) => Err("RELEASE_AUCTION_NEAR_ONLY".to_string()),
+            Some(_) => Err("Token already exists".to_string()),
+        }
+    }
+
+    //the oracle's quote, as long as it is recent enough and within the caller's slippage tolerance
+    fn internal_read_quote(&self, payment: &UsdPayment) -> Result<NearUsdQuote, &'static str> {
+        //the owner may have turned USD pricing off while waiting for the quote
+        let usd_pricing = self.usd_pricing.as_ref().ok_or("USD_PRICING_DISABLED")?;
+        let quote = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<NearUsdQuote>(&value).map_err(|_| "INVALID_QUOTE")?
+            }
+            _ => return Err("ORACLE_FAILED"),
+        };
+        //a quote can't be newer than the block reading it
+        let now = env::block_timestamp();
+        if quote.price.0 == 0 || quote.decimals > MAX_QUOTE_DECIMALS || quote.timestamp.0 > now {
+            return Err("INVALID_QUOTE");
+        }
+        if now - quote.timestamp.0 > usd_pricing.max_quote_age.0 {
+            return Err("STALE_QUOTE");
+        }
+        //a lower NEAR price means paying more NEAR than the caller expected
+        let expected_price = payment.expected_price.0;
+        let tolerance = expected_price
+            .checked_mul(Balance::from(payment.slippage))
+            .ok_or("INVALID_EXPECTED_PRICE")?
+            / Balance::from(MAX_SLIPPAGE);
+        if quote.price.0 < expected_price - tolerance {
+            return Err("QUOTE_SLIPPAGE");
+        }
+        Ok(quote)
+    }
+
+    //a callback can't panic without keeping the deposit, so every failure is refunded instead
+    fn internal_refund_usd_payment(&self, payment: UsdPayment, reason: &str) -> Option<U64> {
+        env::log_str(&format!("Refunding {}: {}", payment.payer_id, reason));
+        Promise::new(payment.payer_id).transfer(payment.deposit.0);
+        None
+    }
+}
+
+//the caller's price has to be usable in the slippage check of the callback
+fn assert_expected_price(expected_price: U128) {
+    assert!(
+        expected_price.0 > 0 && expected_price.0.checked_mul(Balance::from(MAX_SLIPPAGE)).is_some(),
+        "INVALID_EXPECTED_PRICE"
+    );
+}
+
+//the expiry `years` after `from`, unless it doesn't fit in a timestamp
+fn expires_after(from: u64, years: u64) -> Option<u64> {
+    years.checked_mul(ONE_YEAR_NANOSECOND)?.checked_add(from)
+}
+
+//yoctoNEAR worth `cents` at the quoted price, rounded up
+fn usd_cents_to_yocto(cents: Balance, quote: &NearUsdQuote) -> Option<Balance> {
+    let numerator = cents
+        .checked_mul(ONE_NEAR_ES_YOCTO / 100)?
+        .checked_mul(10u128.pow(u32::from(quote.decimals)))?;
+    Some(numerator.checked_add(quote.price.0 - 1)? / quote.price.0)
+}
+
+#[near_bindgen]
+impl UsdPriceCallback for Contract {
+    #[private]
+    fn on_usd_mint(&mut self, payment: UsdPayment, mint: UsdMint) -> Option<U64> {
+        //the deposit is either refunded or pays for the name from here on
+        self.internal_release_user_funds(payment.deposit.0);
+        if let Err(err) = check_referrer(payment.referrer.as_ref(), &payment.payer_id) {
+            return self.internal_refund_usd_payment(payment, err);
+        }
+        let quote = match self.internal_read_quote(&payment) {
+            Ok(quote) => quote,
+            Err(err) => return self.internal_refund_usd_payment(payment, err),
+        };
+        //the name may have been taken while waiting for the quote
+        let domain = DomainName::parse(&mint.token_id).unwrap();
+        if let Err(err) = self.internal_check_usd_registrable(&domain) {
+            return self.internal_refund_usd_payment(payment, &err);
+        }
+
+        let price_per_year = match usd_cents_to_yocto(self.internal_usd_price_per_year(&domain), &quote) {
+            Some(price_per_year) => price_per_year,
+            None => return self.internal_refund_usd_payment(payment, "INVALID_QUOTE"),
+        };
+        let years = match mint.years {
+            Some(years) => u64::from(years),
+            None => u64::try_from(payment.deposit.0 / price_per_year).unwrap_or(u64::MAX),
+        };
+        let price = match price_per_year.checked_mul(Balance::from(years)) {
+            Some(price) if years > 0 && price <= payment.deposit.0 => price,
+            _ => return self.internal_refund_usd_payment(payment, "INVALID_DEPOSIT"),
+        };
+        if expires_after(env::block_timestamp(), years).is_none() {
+            return self.internal_refund_usd_payment(payment, "INVALID_YEARS");
+        }
+
+        self.internal_accrue_revenue(price, payment.referrer.as_ref());
+        let expires_at = self.internal_mint(&domain, mint.metadata, mint.receiver_id, mint.perpetual_royalties, years);
+        if payment.deposit.0 > price {
+            Promise::new(payment.payer_id).transfer(payment.deposit.0 - price);
+        }
+        Some(U64(expires_at))
+    }
+
+    #[private]
+    fn on_usd_renew(&mut self, payment: UsdPayment, token_id: TokenId) -> Option<U64> {
+        self.internal_release_user_funds(payment.deposit.0);
+        if let Err(err) = check_referrer(payment.referrer.as_ref(), &payment.payer_id) {
+            return self.internal_refund_usd_payment(payment, err);
+        }
+        let quote = match self.internal_read_quote(&payment) {
+            Ok(quote) => quote,
+            Err(err) => return self.internal_refund_usd_payment(payment, err),
+        };
+        //the name may have been released while waiting for the quote
+        let domain = DomainName::parse(&token_id).unwrap();
+        if let Err(err) = self.internal_check_renewable(&domain, &payment.payer_id) {
+            return self.internal_refund_usd_payment(payment, err);
+        }
+
+        let price_per_year = match usd_cents_to_yocto(self.internal_usd_price_per_year(&domain), &quote) {
+            Some(price_per_year) => price_per_year,
+            None => return self.internal_refund_usd_payment(payment, "INVALID_QUOTE"),
+        };
+        let years = u64::try_from(payment.deposit.0 / price_per_year).unwrap_or(u64::MAX);
+        if years == 0 {
+            return self.internal_refund_usd_payment(payment, "EXTEND_AT_LEAST_ONE_YEAR");
+        }
+        if expires_after(self.nft_token_expires(token_id.clone()), years).is_none() {
+            return self.internal_refund_usd_payment(payment, "INVALID_YEARS");
+        }
+        //can't overflow, as `years` is at most the deposit divided by the price
+        let price = price_per_year * Balance::from(years);
+
+        self.internal_accrue_revenue(price, payment.referrer.as_ref());
+        let expires_at = self.internal_extend_token(&token_id, years * ONE_YEAR_NANOSECOND);
+        self.internal_log_name_renewed(&token_id, expires_at);
+        if payment.deposit.0 > price {
+            Promise::new(payment.payer_id).transfer(payment.deposit.0 - price);
+        }
+        Some(U64(expires_at))
+    }
+}
@@ -35,4 +35,14 @@ impl Contract {
         );
     }
 
+    //records can also be edited by the manager the owner delegated them to
+    pub(crate) fn assert_token_owner_or_manager(&self, token_id: &TokenId) {
+        let token = self.tokens_by_id.get(token_id).expect("TOKEN_NOT_FOUND");
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == token.owner_id || self.managers_by_token_id.get(token_id).as_ref() == Some(&caller),
+            "ONLY_NFT_OWNER_OR_MANAGER"
+        );
+    }
+
 }
\ No newline at end of file